@@ -0,0 +1,25 @@
+// Reading of arbitrary bytes as a dataset of each format, which must fail
+// with an error rather than panic, now or once the rows go to the solvers.
+//
+//     cargo +nightly fuzz run ingest -- -dict=fuzz/ingest.dict
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_decimal::Decimal;
+
+#[allow(dead_code)]
+#[path = "../../src/ingest.rs"]
+mod ingest;
+
+use ingest::Format;
+
+fuzz_target!(|content: &[u8]| {
+    for format in [Format::Csv, Format::Json, Format::Jsonl, Format::Toml] {
+        if let Ok(rows) = ingest::parse(content, format) {
+            for row in rows {
+                // what Row::from computes
+                let _ = row.price * row.profit / Decimal::from(100);
+            }
+        }
+    }
+});
@@ -0,0 +1,182 @@
+use std::fs::File;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::cancel::Cancellation;
+use crate::risk::Objective;
+use crate::search::Search;
+use crate::{algorithme_name, run_algorithme, sort_data, Row};
+
+#[derive(Debug, Clone)]
+pub struct BenchmarkConfig {
+    pub solvers: Vec<usize>,
+    pub sizes: Vec<usize>,
+    // runs not measured, to warm the caches up
+    pub warmup: usize,
+    pub repetitions: usize,
+    // stops every run at the time limit, and the benchmark on a Ctrl-C
+    pub cancellation: Cancellation,
+    pub time_limit: Option<Duration>,
+}
+
+impl BenchmarkConfig {
+    pub fn new(
+        solvers: Vec<usize>,
+        data_len: usize,
+        from: Option<usize>,
+        to: Option<usize>,
+        step: Option<usize>,
+        warmup: usize,
+        repetitions: usize,
+    ) -> Result<BenchmarkConfig> {
+        let from = from.unwrap_or(2);
+        let to = to.unwrap_or(data_len);
+        let step = step.unwrap_or(if data_len > 50 { 10 } else { 1 });
+        if solvers.is_empty() {
+            bail!("the benchmark needs at least one solver");
+        }
+        if from == 0 || from > to || to > data_len {
+            bail!(
+                "the sizes must be in [1, {}] with from <= to, got {}..{}",
+                data_len,
+                from,
+                to
+            );
+        }
+        if step == 0 || repetitions == 0 {
+            bail!("the step and the number of repetitions must be positive");
+        }
+        Ok(BenchmarkConfig {
+            solvers,
+            sizes: (from..=to).step_by(step).collect(),
+            warmup,
+            repetitions,
+            cancellation: Cancellation::none(),
+            time_limit: None,
+        })
+    }
+
+    pub fn stopped_by(
+        mut self,
+        cancellation: &Cancellation,
+        time_limit: Option<Duration>,
+    ) -> BenchmarkConfig {
+        self.cancellation = cancellation.clone();
+        self.time_limit = time_limit;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Measure {
+    pub solver: usize,
+    pub name: &'static str,
+    pub size: usize,
+    pub repetitions: usize,
+    pub median_ns: u128,
+    pub min_ns: u128,
+    pub max_ns: u128,
+    pub mean_ns: f64,
+    pub stddev_ns: f64,
+    // a run stopped by the time limit or a Ctrl-C, so the durations only
+    // bound the solver
+    pub interrupted: bool,
+}
+
+impl Measure {
+    fn new(solver: usize, size: usize, mut durations: Vec<u128>, interrupted: bool) -> Measure {
+        durations.sort_unstable();
+        let count = durations.len();
+        let median_ns = if count.is_multiple_of(2) {
+            (durations[count / 2 - 1] + durations[count / 2]) / 2
+        } else {
+            durations[count / 2]
+        };
+        let mean_ns = durations.iter().sum::<u128>() as f64 / count as f64;
+        let variance = durations
+            .iter()
+            .map(|d| (*d as f64 - mean_ns).powi(2))
+            .sum::<f64>()
+            / count as f64;
+        Measure {
+            solver,
+            name: algorithme_name(solver),
+            size,
+            repetitions: count,
+            median_ns,
+            min_ns: durations[0],
+            max_ns: durations[count - 1],
+            mean_ns,
+            stddev_ns: variance.sqrt(),
+            interrupted,
+        }
+    }
+}
+
+pub fn run_benchmark(
+    config: &BenchmarkConfig,
+    data: &[Row],
+    balance: Decimal,
+    objective: Objective,
+) -> Result<Vec<Measure>> {
+    let mut measures = Vec::new();
+    let search = || Search::new(config.cancellation.with_time_limit(config.time_limit));
+    for solver in &config.solvers {
+        for size in &config.sizes {
+            // the solvers expect the data sorted by profit, which is not measured
+            let mut reduced_data = data[0..*size].to_vec();
+            sort_data(&mut reduced_data);
+
+            for _ in 0..config.warmup {
+                run_algorithme(*solver, reduced_data.clone(), balance, objective, &search())?;
+            }
+            let mut durations = Vec::new();
+            let mut interrupted = false;
+            for _ in 0..config.repetitions {
+                let input = reduced_data.clone();
+                let start = Instant::now();
+                let best = run_algorithme(*solver, input, balance, objective, &search())?;
+                durations.push(start.elapsed().as_nanos());
+                interrupted |= best.interrupted;
+                if config.cancellation.is_cancelled() {
+                    break;
+                }
+            }
+            let measure = Measure::new(*solver, *size, durations, interrupted);
+            println!(
+                "{} n={} : median {}ns ; min {}ns ; max {}ns ; stddev {:.0}ns{}",
+                measure.name,
+                measure.size,
+                measure.median_ns,
+                measure.min_ns,
+                measure.max_ns,
+                measure.stddev_ns,
+                if measure.interrupted {
+                    " ; interrupted"
+                } else {
+                    ""
+                }
+            );
+            measures.push(measure);
+            // the measures so far are still exported
+            if config.cancellation.is_cancelled() {
+                return Ok(measures);
+            }
+        }
+    }
+    Ok(measures)
+}
+
+pub fn write_csv(path: &str, measures: &[Measure]) -> Result<()> {
+    let file = File::create(path)?;
+    let mut writer = csv::Writer::from_writer(file);
+    for measure in measures {
+        writer.serialize(measure)?;
+    }
+    writer.flush()?;
+    println!("Benchmark written to {}", path);
+    Ok(())
+}
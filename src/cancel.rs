@@ -0,0 +1,92 @@
+use std::cell::Cell;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+
+// Asks a solver to stop, from another thread (Ctrl-C) or once its time limit
+// is over. A stopped solver returns the best selection found so far, which is
+// then marked as interrupted since it is not proven optimal.
+#[derive(Debug, Clone, Default)]
+pub struct Cancellation {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+    // set once a solver stopped because of the cancellation
+    stopped: Cell<bool>,
+}
+
+impl Cancellation {
+    // Never cancelled
+    pub fn none() -> Cancellation {
+        Cancellation::default()
+    }
+
+    // Cancelled by a first Ctrl-C, the second one exiting as usual, so that a
+    // command which does not watch the cancellation can still be stopped
+    pub fn on_ctrl_c() -> Result<Cancellation> {
+        let cancellation = Cancellation::none();
+        let cancelled = cancellation.cancelled.clone();
+        ctrlc::set_handler(move || {
+            if cancelled.swap(true, Ordering::SeqCst) {
+                process::exit(130);
+            }
+            eprintln!("Interrupted, stopping with the best result so far (Ctrl-C again to exit)");
+        })?;
+        Ok(cancellation)
+    }
+
+    // The same cancellation, with a deadline counted from now
+    pub fn with_time_limit(&self, time_limit: Option<Duration>) -> Cancellation {
+        Cancellation {
+            cancelled: self.cancelled.clone(),
+            deadline: time_limit.map(|time_limit| Instant::now() + time_limit),
+            stopped: Cell::new(false),
+        }
+    }
+
+    // Polled by the solvers as they search
+    pub fn should_stop(&self) -> bool {
+        let stop = self.cancelled.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+        if stop {
+            self.stopped.set(true);
+        }
+        stop
+    }
+
+    // Whether a solver stopped before the end of its search
+    pub fn stopped(&self) -> bool {
+        self.stopped.get()
+    }
+
+    // Whether a Ctrl-C asked to stop, which ends the commands solving several
+    // times rather than only their current solve
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    // Ready to solve again after a Ctrl-C, which then stops one solve only
+    pub fn reset(&self) {
+        self.cancelled.store(false, Ordering::SeqCst);
+    }
+
+    #[cfg(test)]
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+pub fn parse_time_limit(seconds: &str) -> Result<Duration> {
+    let seconds: f64 = seconds.parse()?;
+    if !seconds.is_finite() || seconds < 0.0 {
+        bail!(
+            "the time limit must be a positive number of seconds, got {}",
+            seconds
+        );
+    }
+    Ok(Duration::from_secs_f64(seconds))
+}
@@ -0,0 +1,145 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use clap::ArgEnum;
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartFormat {
+    Png,
+    Svg,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    En,
+    Fr,
+}
+
+// Captions of the duration chart
+pub struct Labels {
+    pub title: &'static str,
+    pub x_desc: &'static str,
+    pub y_desc: &'static str,
+}
+
+// Captions of the portfolio charts
+pub struct PortfolioLabels {
+    pub title: &'static str,
+    pub bars: &'static str,
+    pub cost: &'static str,
+    pub benefit: &'static str,
+    pub scatter: &'static str,
+    pub price: &'static str,
+    pub profit: &'static str,
+    pub chosen: &'static str,
+    pub rejected: &'static str,
+    pub pie: &'static str,
+    pub leftover: &'static str,
+}
+
+impl Language {
+    pub fn portfolio_labels(&self) -> PortfolioLabels {
+        match self {
+            Language::En => PortfolioLabels {
+                title: "Portfolio",
+                bars: "Cost and benefit of the chosen shares",
+                cost: "Cost (€)",
+                benefit: "Benefit (€)",
+                scatter: "Shares by price and profit",
+                price: "Price (€)",
+                profit: "Profit (%)",
+                chosen: "chosen",
+                rejected: "rejected",
+                pie: "Budget split",
+                leftover: "Leftover",
+            },
+            Language::Fr => PortfolioLabels {
+                title: "Portefeuille",
+                bars: "Coût et bénéfice des actions choisies",
+                cost: "Coût (€)",
+                benefit: "Bénéfice (€)",
+                scatter: "Actions par prix et rendement",
+                price: "Prix (€)",
+                profit: "Rendement (%)",
+                chosen: "choisies",
+                rejected: "rejetées",
+                pie: "Répartition du budget",
+                leftover: "Reste",
+            },
+        }
+    }
+
+    pub fn labels(&self) -> Labels {
+        match self {
+            Language::En => Labels {
+                title: "Execution time / number of actions",
+                x_desc: "Number of actions",
+                y_desc: "Duration (ms)",
+            },
+            Language::Fr => Labels {
+                title: "Temps d'exécution / nombre d'actions",
+                x_desc: "Nombre d'actions",
+                y_desc: "Durée (ms)",
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChartOptions {
+    pub output: String,
+    pub size: (u32, u32),
+    pub format: ChartFormat,
+    pub language: Language,
+    pub title: Option<String>,
+    pub log_scale: bool,
+}
+
+impl ChartOptions {
+    // Without an output, the chart is named after what it shows (`default_name`)
+    // so that charts of different solvers do not overwrite each other. Without
+    // a format, it is guessed from the extension of the output.
+    pub fn new(
+        output: Option<String>,
+        default_name: &str,
+        size: (u32, u32),
+        format: Option<ChartFormat>,
+        language: Language,
+        title: Option<String>,
+        log_scale: bool,
+    ) -> ChartOptions {
+        let format = format.unwrap_or_else(|| match &output {
+            Some(output) if output.to_lowercase().ends_with(".svg") => ChartFormat::Svg,
+            _ => ChartFormat::Png,
+        });
+        let output = output.unwrap_or_else(|| {
+            let extension = match format {
+                ChartFormat::Png => "png",
+                ChartFormat::Svg => "svg",
+            };
+            Path::new("explanations")
+                .join(format!("{}.{}", default_name, extension))
+                .to_string_lossy()
+                .into_owned()
+        });
+        ChartOptions {
+            output,
+            size,
+            format,
+            language,
+            title,
+            log_scale,
+        }
+    }
+
+    pub fn title(&self, default: &str) -> String {
+        self.title.clone().unwrap_or_else(|| default.to_string())
+    }
+}
+
+pub fn parse_size(size: &str) -> Result<(u32, u32)> {
+    match size.split_once('x') {
+        Some((width, height)) => Ok((width.trim().parse()?, height.trim().parse()?)),
+        None => bail!("expected a size like 600x400, got {}", size),
+    }
+}
@@ -0,0 +1,332 @@
+use std::ffi::OsString;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use clap::{
+    ArgMatches, Args, CommandFactory, ErrorKind, FromArgMatches, Parser, Subcommand, ValueSource,
+};
+use rust_decimal::Decimal;
+
+use crate::cancel;
+use crate::chart::{self, ChartFormat, Language};
+use crate::compare::CompareArgs;
+use crate::config::Profile;
+use crate::curves::Reference;
+use crate::diff::DiffArgs;
+use crate::explore::ExploreArgs;
+use crate::generate::GenerateArgs;
+use crate::ingest::Format;
+use crate::risk::{self, Objective};
+use crate::serve::ServeArgs;
+use crate::sweep::SweepArgs;
+use crate::validate::ValidateArgs;
+
+/// Without a subcommand, the flags of the former interface solve the dataset,
+/// or benchmark it with --curves or --bench.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+pub struct Cli {
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+    /// TOML file of the profiles
+    #[clap(long, global = true, default_value = "oc_trade.toml")]
+    pub config: String,
+    /// Profile of the configuration whose values replace the defaults of the flags not given
+    #[clap(long, global = true)]
+    pub profile: Option<String>,
+    #[clap(flatten)]
+    pub legacy: LegacyArgs,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    Solve(SolveArgs),
+    Bench(BenchArgs),
+    Compare(CompareArgs),
+    #[clap(alias = "stats")]
+    Validate(ValidateArgs),
+    Generate(GenerateArgs),
+    Sweep(SweepArgs),
+    Serve(ServeArgs),
+    #[clap(alias = "repl")]
+    Explore(ExploreArgs),
+    Diff(DiffArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SourceArgs {
+    /// Number N of the dataset/dataset<N>_Python+P7.csv file
+    #[clap(short, long, default_value_t = 0)]
+    pub dataset: usize,
+    /// Dataset file read instead of dataset N (CSV, JSON, JSON Lines or TOML)
+    #[clap(long, conflicts_with = "dataset")]
+    pub input: Option<String>,
+    /// Format of the dataset (csv, json, jsonl or toml), guessed from the extension by default
+    #[clap(long)]
+    pub format: Option<Format>,
+}
+
+impl SourceArgs {
+    // dataset N in another format has the extension of the format
+    pub fn path(&self) -> String {
+        match &self.input {
+            Some(input) => input.clone(),
+            None => format!(
+                "dataset/dataset{}_Python+P7.{}",
+                self.dataset,
+                self.format.unwrap_or(Format::Csv).extension()
+            ),
+        }
+    }
+
+    pub fn format(&self) -> Result<Format> {
+        let path = self.path();
+        match self.format.or_else(|| Format::from_path(&path)) {
+            Some(format) => Ok(format),
+            None => bail!("unknown format of {}, give it with --format", path),
+        }
+    }
+
+    // For the files named after the dataset
+    pub fn name(&self) -> String {
+        match &self.input {
+            Some(input) => Path::new(input)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| input.clone()),
+            None => format!("dataset{}", self.dataset),
+        }
+    }
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DatasetArgs {
+    /// Budget in euros
+    #[clap(short, long, default_value_t = 500)]
+    pub balance: usize,
+    #[clap(flatten)]
+    pub source: SourceArgs,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ObjectiveArgs {
+    /// Maximise benefits − λ·risk, λ ≥ 0 (risk solvers 5 and 6)
+    #[clap(long, conflicts_with = "max-risk", parse(try_from_str = risk::parse_lambda))]
+    pub lambda: Option<Decimal>,
+    /// Maximise benefits with a total risk ≤ R, R ≥ 0 (risk solvers 5 and 6)
+    #[clap(long, parse(try_from_str = risk::parse_max_risk))]
+    pub max_risk: Option<Decimal>,
+}
+
+impl ObjectiveArgs {
+    pub fn objective(&self) -> Objective {
+        match (self.lambda, self.max_risk) {
+            (Some(lambda), _) => Objective::Penalized { lambda },
+            (_, Some(max_risk)) => Objective::RiskBudget { max_risk },
+            _ => Objective::Benefits,
+        }
+    }
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ChartArgs {
+    /// Image of the chart (in explanations/, named after its content, by default)
+    #[clap(long)]
+    pub chart_output: Option<String>,
+    /// Size of the charts in pixels (600x400 for the durations, 1200x900 for the portfolio by default)
+    #[clap(long, parse(try_from_str = chart::parse_size))]
+    pub chart_size: Option<(u32, u32)>,
+    /// Format of the chart, guessed from the extension of the output by default
+    #[clap(long, arg_enum)]
+    pub chart_format: Option<ChartFormat>,
+    /// Language of the captions of the chart
+    #[clap(long, arg_enum, default_value = "fr")]
+    pub lang: Language,
+    /// Title of the chart
+    #[clap(long)]
+    pub chart_title: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SizesArgs {
+    /// Smallest number of actions benchmarked (2 by default)
+    #[clap(long)]
+    pub sizes_from: Option<usize>,
+    /// Largest number of actions benchmarked (all the dataset by default)
+    #[clap(long)]
+    pub sizes_to: Option<usize>,
+    /// Step between two numbers of actions benchmarked (10 above 50 actions, else 1)
+    #[clap(long)]
+    pub sizes_step: Option<usize>,
+    /// Runs before the measures of each size
+    #[clap(long, default_value_t = 1)]
+    pub warmup: usize,
+    /// Measured runs of each size
+    #[clap(long, default_value_t = 5)]
+    pub repetitions: usize,
+    /// CSV file of the benchmark (dataset/curves/curve_complexity_dataset<N>.csv by default)
+    #[clap(long)]
+    pub bench_output: Option<String>,
+}
+
+/// Solve a dataset with one solver, and explain, simulate or chart the result
+#[derive(Args, Debug, Clone)]
+pub struct SolveArgs {
+    #[clap(flatten)]
+    pub dataset: DatasetArgs,
+    #[clap(short, long, default_value_t = 0)]
+    pub algorithme: usize,
+    #[clap(flatten)]
+    pub objective: ObjectiveArgs,
+    /// Seconds given to the solver, which then returns its best result so far
+    #[clap(long, parse(try_from_str = cancel::parse_time_limit))]
+    pub time_limit: Option<Duration>,
+    /// Show the progress of the search
+    #[clap(long, takes_value = false)]
+    pub progress: bool,
+    /// Stream each improvement of the best result as a JSON line to this file (`-` for stdout)
+    #[clap(long, conflicts_with = "holdings")]
+    pub json_lines: Option<String>,
+    /// CSV file (`name` column, `line` to tell apart equal names) or JSON result (solve --json) of the shares already held, to rebalance them
+    #[clap(long)]
+    pub holdings: Option<String>,
+    /// Fees in percent of the price, paid when selling a held share
+    #[clap(long, default_value = "0")]
+    pub sell_fees: Decimal,
+    /// Number of samples of the profits (within profit_min/profit_max) to simulate
+    #[clap(long, conflicts_with = "holdings")]
+    pub simulate: Option<usize>,
+    /// Seed of the simulation
+    #[clap(long, default_value_t = 0)]
+    pub seed: u64,
+    /// Show the profit (%) of each share where the selection changes
+    #[clap(long, conflicts_with = "holdings", takes_value = false)]
+    pub sensitivity: bool,
+    /// Explain why each share was bought or rejected
+    #[clap(long, conflicts_with = "holdings", takes_value = false)]
+    pub explain: bool,
+    /// Chart the solved portfolio (explanations/portfolio_dataset<N>.png by default)
+    #[clap(long, conflicts_with = "holdings", min_values = 0)]
+    pub portfolio_chart: Option<Option<String>>,
+    /// Write a self-contained HTML report of the run
+    #[clap(long, conflicts_with = "holdings")]
+    pub report: Option<String>,
+    /// Write the result as the JSON answered by POST /solve, which diff compares
+    #[clap(long, conflicts_with = "holdings")]
+    pub json: Option<String>,
+    #[clap(flatten)]
+    pub chart: ChartArgs,
+}
+
+/// Measure the durations of solvers on growing parts of a dataset
+#[derive(Args, Debug, Clone)]
+pub struct BenchArgs {
+    #[clap(flatten)]
+    pub dataset: DatasetArgs,
+    /// Solvers benchmarked (comma separated)
+    #[clap(short, long, use_value_delimiter = true, default_value = "0")]
+    pub solvers: Vec<usize>,
+    #[clap(flatten)]
+    pub objective: ObjectiveArgs,
+    #[clap(flatten)]
+    pub sizes: SizesArgs,
+    /// Seconds given to each run, which then counts as interrupted
+    #[clap(long, parse(try_from_str = cancel::parse_time_limit))]
+    pub time_limit: Option<Duration>,
+    /// Chart the durations besides the CSV export
+    #[clap(long, takes_value = false)]
+    pub chart: bool,
+    /// Reference complexities fitted and drawn on the chart (comma separated, the best fitting one of each solver by default)
+    #[clap(long, arg_enum, use_value_delimiter = true)]
+    pub reference: Vec<Reference>,
+    /// Logarithmic scale for the durations of the chart
+    #[clap(long, takes_value = false)]
+    pub log_scale: bool,
+    #[clap(flatten)]
+    pub chart_args: ChartArgs,
+}
+
+// The flat flags of the former interface, kept for the existing scripts
+#[derive(Args, Debug)]
+pub struct LegacyArgs {
+    #[clap(flatten)]
+    solve: SolveArgs,
+    /// Chart the durations of the given solvers (comma separated, the algorithme by default)
+    #[clap(short, long, use_value_delimiter = true, min_values = 0)]
+    curves: Option<Vec<usize>>,
+    /// Benchmark the given solvers (comma separated) and export the timings to CSV
+    #[clap(long, use_value_delimiter = true)]
+    bench: Option<Vec<usize>>,
+    /// Reference complexities fitted and drawn on the chart (comma separated, the best fitting one of each solver by default)
+    #[clap(long, arg_enum, use_value_delimiter = true)]
+    reference: Vec<Reference>,
+    /// Logarithmic scale for the durations of the chart
+    #[clap(long, takes_value = false)]
+    log_scale: bool,
+    #[clap(flatten)]
+    sizes: SizesArgs,
+}
+
+// Given on either side of a subcommand, unlike the flags of the former interface
+const GLOBAL_ARGS: [&str; 4] = ["config", "profile", "help", "version"];
+
+impl Cli {
+    // The command line parsed, along with its matches which tell the flags
+    // given from the defaults for the profiles
+    pub fn parse_with_matches<I, T>(args: I) -> Result<(Cli, ArgMatches), clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let mut command = Cli::command();
+        let matches = command.try_get_matches_from_mut(args)?;
+        if let Some((name, _)) = matches.subcommand() {
+            let legacy = command
+                .get_arguments()
+                .map(|arg| arg.get_id())
+                .filter(|id| !GLOBAL_ARGS.contains(id))
+                .find(|id| matches.value_source(*id) == Some(ValueSource::CommandLine));
+            if let Some(id) = legacy {
+                return Err(command.error(
+                    ErrorKind::ArgumentConflict,
+                    format!(
+                        "--{} belongs to the former interface and cannot be given before the subcommand {}",
+                        id, name
+                    ),
+                ));
+            }
+        }
+        let cli = Cli::from_arg_matches(&matches)?;
+        Ok((cli, matches))
+    }
+}
+
+impl LegacyArgs {
+    pub fn apply_profile(&mut self, profile: &Profile, matches: &ArgMatches) {
+        profile.apply_solve(&mut self.solve, matches);
+    }
+
+    // --curves and --bench benchmark, anything else solves
+    pub fn into_command(self) -> Command {
+        if self.curves.is_none() && self.bench.is_none() {
+            return Command::Solve(self.solve);
+        }
+        let solvers = match (&self.curves, self.bench) {
+            (Some(solvers), _) if !solvers.is_empty() => solvers.clone(),
+            (_, Some(solvers)) => solvers,
+            _ => vec![self.solve.algorithme],
+        };
+        Command::Bench(BenchArgs {
+            dataset: self.solve.dataset,
+            solvers,
+            objective: self.solve.objective,
+            sizes: self.sizes,
+            time_limit: self.solve.time_limit,
+            chart: self.curves.is_some(),
+            reference: self.reference,
+            log_scale: self.log_scale,
+            chart_args: self.solve.chart,
+        })
+    }
+}
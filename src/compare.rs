@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use clap::Args;
+use rust_decimal::Decimal;
+
+use crate::cancel::{self, Cancellation};
+use crate::cli::{DatasetArgs, ObjectiveArgs};
+use crate::search::Search;
+use crate::{algorithme_name, load_dataset, run_algorithme, sort_data, verify, Best};
+
+/// Solve the same dataset with several solvers and compare their results
+#[derive(Args, Debug, Clone)]
+pub struct CompareArgs {
+    #[clap(flatten)]
+    pub dataset: DatasetArgs,
+    /// Solvers compared (comma separated)
+    #[clap(short, long, use_value_delimiter = true, default_value = "0,1,2,3,4")]
+    pub solvers: Vec<usize>,
+    #[clap(flatten)]
+    pub objective: ObjectiveArgs,
+    /// Seconds given to each solver, which then returns its best result so far
+    #[clap(long, parse(try_from_str = cancel::parse_time_limit))]
+    pub time_limit: Option<Duration>,
+}
+
+pub fn compare(args: &CompareArgs, cancellation: &Cancellation) -> Result<()> {
+    let balance: Decimal = args.dataset.balance.into();
+    let objective = args.objective.objective();
+    let (mut data, _) = load_dataset(&args.dataset.source)?;
+    sort_data(&mut data);
+
+    let mut results: Vec<(usize, Best, Duration)> = Vec::new();
+    for solver in &args.solvers {
+        let start = Instant::now();
+        let search = Search::new(cancellation.with_time_limit(args.time_limit));
+        let best = run_algorithme(*solver, data.clone(), balance, objective, &search)?;
+        results.push((*solver, best, start.elapsed()));
+    }
+
+    println!(
+        "{:<32} {:>12} {:>10} {:>10} {:>7} {:>14}  checked",
+        "solver", "earnings", "spent", "leftover", "shares", "duration"
+    );
+    for (solver, best, duration) in &results {
+        let verification = verify(best, &data, balance);
+        let checked = verification.earnings_match(best)
+            && verification.balance_match(best)
+            && verification.within_budget();
+        println!(
+            "{:<32} {:>12} {:>10} {:>10} {:>7} {:>14}  {}",
+            algorithme_name(*solver),
+            best.earnings.round_dp(2),
+            balance - best.balance,
+            best.balance,
+            best.actions.len(),
+            format!("{:?}", duration),
+            match (checked, best.interrupted) {
+                (false, _) => "FAILED",
+                (true, false) => "ok",
+                (true, true) => "ok, interrupted",
+            }
+        );
+    }
+
+    // the solvers are compared to the best earnings found by any of them
+    if let Some(reference) = results.iter().map(|(_, best, _)| best.earnings).max() {
+        for (solver, best, _) in &results {
+            if best.earnings < reference {
+                println!(
+                    "{} is {} below the best earnings",
+                    algorithme_name(*solver),
+                    (reference - best.earnings).round_dp(6)
+                );
+            }
+        }
+        let mut selections: Vec<Vec<usize>> = results
+            .iter()
+            .map(|(_, best, _)| {
+                let mut actions = best.actions.clone();
+                actions.sort_unstable();
+                actions
+            })
+            .collect();
+        selections.sort();
+        selections.dedup();
+        if selections.len() == 1 {
+            println!("All the solvers chose the same shares");
+        } else {
+            println!(
+                "The solvers chose {} different selections",
+                selections.len()
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use anyhow::{bail, Result};
+use clap::{ArgMatches, ValueSource};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::cli::{BenchArgs, Command, DatasetArgs, ObjectiveArgs, SolveArgs};
+use crate::risk;
+
+// A file of named profiles, like
+//
+// [profile.sienna]
+// balance = 500
+// dataset = 1
+// algorithme = 3
+// sell-fees = 0.5
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    profile: BTreeMap<String, Profile>,
+}
+
+// Values used for the flags which are not given on the command line
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Profile {
+    balance: Option<usize>,
+    dataset: Option<usize>,
+    algorithme: Option<usize>,
+    lambda: Option<Decimal>,
+    max_risk: Option<Decimal>,
+    sell_fees: Option<Decimal>,
+    holdings: Option<String>,
+}
+
+pub fn load_profile(path: &str, name: &str) -> Result<Profile> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(error) => bail!("cannot read the configuration {} : {}", path, error),
+    };
+    let config: ConfigFile = match toml::from_str(&content) {
+        Ok(config) => config,
+        Err(error) => bail!("invalid configuration {} : {}", path, error),
+    };
+    match config.profile.get(name) {
+        Some(profile) if profile.lambda.is_some() && profile.max_risk.is_some() => {
+            bail!("profile {} cannot set both lambda and max-risk", name)
+        }
+        Some(profile) => {
+            let weights = [("lambda", profile.lambda), ("max-risk", profile.max_risk)];
+            for (key, value) in weights {
+                if let Some(value) = value {
+                    if let Err(error) = risk::check_weight(key, value) {
+                        bail!("profile {} : {}", name, error);
+                    }
+                }
+            }
+            Ok(profile.clone())
+        }
+        None => {
+            let names: Vec<&str> = config.profile.keys().map(|name| name.as_str()).collect();
+            bail!(
+                "no profile {} in {}, the profiles are : {}",
+                name,
+                path,
+                names.join(", ")
+            )
+        }
+    }
+}
+
+fn from_command_line(matches: &ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+fn set<T: Clone>(value: &mut T, profile: &Option<T>, matches: &ArgMatches, id: &str) {
+    if let Some(profile) = profile {
+        if !from_command_line(matches, id) {
+            *value = profile.clone();
+        }
+    }
+}
+
+impl Profile {
+    fn apply_dataset(&self, args: &mut DatasetArgs, matches: &ArgMatches) {
+        set(&mut args.balance, &self.balance, matches, "balance");
+        set(&mut args.source.dataset, &self.dataset, matches, "dataset");
+    }
+
+    // The objective of the command line replaces the whole objective of the
+    // profile, --lambda and --max-risk being exclusive
+    fn apply_objective(&self, args: &mut ObjectiveArgs, matches: &ArgMatches) {
+        if from_command_line(matches, "lambda") || from_command_line(matches, "max-risk") {
+            return;
+        }
+        if self.lambda.is_some() || self.max_risk.is_some() {
+            args.lambda = self.lambda;
+            args.max_risk = self.max_risk;
+        }
+    }
+
+    pub fn apply_solve(&self, args: &mut SolveArgs, matches: &ArgMatches) {
+        self.apply_dataset(&mut args.dataset, matches);
+        self.apply_objective(&mut args.objective, matches);
+        set(
+            &mut args.algorithme,
+            &self.algorithme,
+            matches,
+            "algorithme",
+        );
+        set(&mut args.sell_fees, &self.sell_fees, matches, "sell-fees");
+        if self.holdings.is_some() && !from_command_line(matches, "holdings") {
+            args.holdings = self.holdings.clone();
+        }
+    }
+
+    fn apply_bench(&self, args: &mut BenchArgs, matches: &ArgMatches) {
+        self.apply_dataset(&mut args.dataset, matches);
+        self.apply_objective(&mut args.objective, matches);
+        if let Some(algorithme) = self.algorithme {
+            if !from_command_line(matches, "solvers") {
+                args.solvers = vec![algorithme];
+            }
+        }
+    }
+
+    // `matches` are the ones of the subcommand, or of the whole command line
+    // for the former interface
+    pub fn apply(&self, command: &mut Command, matches: &ArgMatches) {
+        match command {
+            Command::Solve(args) => self.apply_solve(args, matches),
+            Command::Bench(args) => self.apply_bench(args, matches),
+            Command::Compare(args) => {
+                self.apply_dataset(&mut args.dataset, matches);
+                self.apply_objective(&mut args.objective, matches);
+            }
+            Command::Sweep(args) => {
+                set(&mut args.source.dataset, &self.dataset, matches, "dataset");
+                set(
+                    &mut args.algorithme,
+                    &self.algorithme,
+                    matches,
+                    "algorithme",
+                );
+                self.apply_objective(&mut args.objective, matches);
+            }
+            Command::Explore(args) => {
+                self.apply_dataset(&mut args.dataset, matches);
+                self.apply_objective(&mut args.objective, matches);
+                set(
+                    &mut args.algorithme,
+                    &self.algorithme,
+                    matches,
+                    "algorithme",
+                );
+            }
+            Command::Diff(args) => {
+                self.apply_dataset(&mut args.dataset, matches);
+                self.apply_objective(&mut args.objective, matches);
+            }
+            Command::Validate(args) => self.apply_dataset(&mut args.dataset, matches),
+            Command::Generate(_) | Command::Serve(_) => {}
+        }
+    }
+}
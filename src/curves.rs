@@ -0,0 +1,253 @@
+use std::ops::Range;
+
+use anyhow::Result;
+use clap::ArgEnum;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use crate::algorithme_name;
+use crate::benchmark::{self, BenchmarkConfig, Measure};
+use crate::chart::{ChartFormat, ChartOptions};
+use crate::fitting::{self, Fit};
+use crate::risk::Objective;
+use crate::Row;
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reference {
+    #[clap(name = "n")]
+    Linear,
+    #[clap(name = "nlogn")]
+    Linearithmic,
+    #[clap(name = "n2")]
+    Quadratic,
+    // pseudo-polynomial, W being the balance
+    #[clap(name = "nw")]
+    PseudoPolynomial,
+    #[clap(name = "2n")]
+    Exponential,
+}
+
+impl Reference {
+    // logarithm of the complexity, which stays finite for 2^n with a large n
+    pub fn ln_complexity(&self, n: f64, balance: f64) -> f64 {
+        match self {
+            Reference::Linear => n.ln(),
+            Reference::Linearithmic => n.ln() + n.log2().ln(),
+            Reference::Quadratic => 2.0 * n.ln(),
+            Reference::PseudoPolynomial => n.ln() + balance.ln(),
+            Reference::Exponential => n * 2f64.ln(),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Reference::Linear => "O(k*n)",
+            Reference::Linearithmic => "O(k*n*log(n))",
+            Reference::Quadratic => "O(k*n^2)",
+            Reference::PseudoPolynomial => "O(k*n*W)",
+            Reference::Exponential => "O(k*2^n)",
+        }
+    }
+}
+
+// (number of actions, duration in milliseconds)
+type Points = Vec<(usize, f64)>;
+
+pub fn curve_duration(
+    config: &BenchmarkConfig,
+    data: Vec<Row>,
+    balance: Decimal,
+    objective: Objective,
+    output: &str,
+    references: &[Reference],
+    options: &ChartOptions,
+) -> Result<()> {
+    let measures = benchmark::run_benchmark(config, &data, balance, objective)?;
+    benchmark::write_csv(output, &measures)?;
+
+    // median durations in milliseconds by number of actions, for each solver
+    let durations: Vec<(usize, Points)> = config
+        .solvers
+        .iter()
+        .map(|solver| {
+            let points = measures
+                .iter()
+                .filter(|measure| measure.solver == *solver)
+                .map(|measure: &Measure| (measure.size, measure.median_ns as f64 / 1_000_000.0))
+                .collect();
+            (*solver, points)
+        })
+        .collect();
+    let first_size = config.sizes[0];
+    let last_size = config.sizes[config.sizes.len() - 1];
+    let all_durations = durations
+        .iter()
+        .flat_map(|(_, points)| points.iter().map(|(_, duration)| *duration));
+    let max_duration = all_durations.clone().fold(0.0, f64::max);
+    let min_duration = all_durations.fold(f64::INFINITY, f64::min);
+
+    // Without references, the best fitting complexity of each solver is
+    // drawn, else the references are fitted on the first solver.
+    let balance = balance.to_f64().unwrap();
+    let fits: Vec<Fit> = if references.is_empty() {
+        durations
+            .iter()
+            .filter_map(|(_, points)| fitting::fit_all(points, balance).first().copied())
+            .collect()
+    } else {
+        references
+            .iter()
+            .filter_map(|reference| fitting::fit(*reference, &durations[0].1, balance))
+            .collect()
+    };
+    fitting::show_fits(&config.solvers, &measures, balance);
+    let fitted: Vec<(Fit, Points)> = fits
+        .into_iter()
+        .map(|fit| {
+            let points = config
+                .sizes
+                .iter()
+                .map(|n| (*n, fit.predict(*n)))
+                .filter(|(_, y)| y.is_finite() && *y > 0.0 && *y <= max_duration * 10.0)
+                .collect();
+            (fit, points)
+        })
+        .collect();
+
+    let log_scale = options.log_scale;
+    let y_range = if log_scale {
+        min_duration.log10().floor()..(max_duration * 1.1).log10().ceil()
+    } else {
+        0.0..max_duration * 1.1
+    };
+    let chart = DurationChart {
+        durations,
+        fitted,
+        x_range: first_size..last_size + 1,
+        y_range,
+        log_scale,
+    };
+
+    let size = options.size;
+    match options.format {
+        ChartFormat::Png => chart.draw(
+            BitMapBackend::new(&options.output, size).into_drawing_area(),
+            options,
+        )?,
+        ChartFormat::Svg => chart.draw(
+            SVGBackend::new(&options.output, size).into_drawing_area(),
+            options,
+        )?,
+    }
+    println!("Chart written to {}", options.output);
+
+    Ok(())
+}
+
+struct DurationChart {
+    durations: Vec<(usize, Points)>,
+    fitted: Vec<(Fit, Points)>,
+    x_range: Range<usize>,
+    y_range: Range<f64>,
+    // the durations are drawn by their logarithm
+    log_scale: bool,
+}
+
+impl DurationChart {
+    fn scale(&self, duration: f64) -> f64 {
+        if self.log_scale {
+            duration.log10()
+        } else {
+            duration
+        }
+    }
+
+    fn draw<DB: DrawingBackend>(
+        &self,
+        root_area: DrawingArea<DB, Shift>,
+        options: &ChartOptions,
+    ) -> Result<()>
+    where
+        DB::ErrorType: 'static,
+    {
+        let labels = options.language.labels();
+        root_area.fill(&WHITE)?;
+
+        let mut ctx = ChartBuilder::on(&root_area)
+            .set_label_area_size(LabelAreaPosition::Left, 60)
+            .set_label_area_size(LabelAreaPosition::Bottom, 40)
+            .caption(options.title(labels.title), ("sans-serif", 30))
+            .build_cartesian_2d(self.x_range.clone(), self.y_range.clone())?;
+
+        let y_label = |y: &f64| {
+            if self.log_scale {
+                format!("{:.0e}", 10f64.powf(*y))
+            } else {
+                format!("{}", y)
+            }
+        };
+        ctx.configure_mesh()
+            .x_desc(labels.x_desc)
+            .y_desc(labels.y_desc)
+            .y_label_formatter(&y_label)
+            .draw()?;
+
+        for (i, (solver, points)) in self.durations.iter().enumerate() {
+            let color = Palette99::pick(i).to_rgba();
+            ctx.draw_series(LineSeries::new(
+                points
+                    .iter()
+                    .map(|(n, duration)| (*n, self.scale(*duration))),
+                color.stroke_width(2),
+            ))?
+            .label(algorithme_name(*solver))
+            .legend(move |(x, y)| {
+                PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2))
+            });
+
+            // a single curve keeps the four last measures annotated, several
+            // curves only their last one so the labels do not overlap
+            let annotated = if self.durations.len() == 1 { 4 } else { 1 };
+            ctx.draw_series(PointSeries::of_element(
+                points
+                    .iter()
+                    .rev()
+                    .take(annotated)
+                    .map(|(n, duration)| (*n, self.scale(*duration), *duration)),
+                4,
+                ShapeStyle::from(&color).filled(),
+                &|(n, y, duration), size, style| {
+                    let label = if duration >= 1000.0 {
+                        format!("{}: {:.2}s", n, duration / 1000.0)
+                    } else {
+                        format!("{}: {:.2}ms", n, duration)
+                    };
+                    EmptyElement::at((n, y))
+                        + Circle::new((0, 0), size, style)
+                        + Text::new(label, (-69, -4), ("sans-serif", 15))
+                },
+            ))?;
+        }
+
+        for (i, (fit, points)) in self.fitted.iter().enumerate() {
+            let color = Palette99::pick(self.durations.len() + i).mix(0.5);
+            ctx.draw_series(LineSeries::new(
+                points.iter().map(|(n, y)| (*n, self.scale(*y))),
+                color,
+            ))?
+            .label(fit.label())
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+        }
+
+        ctx.configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .position(SeriesLabelPosition::UpperLeft)
+            .draw()?;
+
+        root_area.present()?;
+        Ok(())
+    }
+}
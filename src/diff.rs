@@ -0,0 +1,231 @@
+use std::fs;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use clap::Args;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::cancel::{self, Cancellation};
+use crate::cli::{DatasetArgs, ObjectiveArgs};
+use crate::search::Search;
+use crate::{algorithme_name, load_dataset, run_algorithme, sort_data, Best, ShareId, ALGORITHMES};
+
+/// Compare two results : two JSON files written by solve --json (or answered by
+/// POST /solve), or two solvers on the same dataset
+#[derive(Args, Debug, Clone)]
+pub struct DiffArgs {
+    /// JSON results compared, the first one being the reference
+    #[clap(conflicts_with = "solvers")]
+    pub results: Vec<String>,
+    /// Solvers compared on the dataset, by number or name (comma separated), the first one being the reference
+    #[clap(short, long, use_value_delimiter = true)]
+    pub solvers: Vec<String>,
+    #[clap(flatten)]
+    pub dataset: DatasetArgs,
+    #[clap(flatten)]
+    pub objective: ObjectiveArgs,
+    /// Seconds given to each solver, which then returns its best result so far
+    #[clap(long, parse(try_from_str = cancel::parse_time_limit))]
+    pub time_limit: Option<Duration>,
+}
+
+// A result as the diffs compare it, its shares being known by their name and
+// line, so that two rows of the same name are told apart
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    pub shares: Vec<ShareId>,
+    pub cost: Decimal,
+    pub earnings: Decimal,
+    pub leftover: Decimal,
+}
+
+impl Selection {
+    pub fn new(best: &Best, balance: Decimal) -> Selection {
+        Selection {
+            shares: best.shares.clone(),
+            cost: balance - best.balance,
+            earnings: best.earnings,
+            leftover: best.balance,
+        }
+    }
+}
+
+// What diff reads of a JSON result, the other fields being ignored
+#[derive(Deserialize)]
+struct ResultFile {
+    shares: Vec<ShareId>,
+    spent: Decimal,
+    earnings: Decimal,
+    leftover: Decimal,
+}
+
+impl Selection {
+    pub fn from_json(content: &str) -> Result<Selection> {
+        let result: ResultFile = serde_json::from_str(content)?;
+        Ok(Selection {
+            shares: result.shares,
+            cost: result.spent,
+            earnings: result.earnings,
+            leftover: result.leftover,
+        })
+    }
+
+    fn read(path: &str) -> Result<Selection> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(error) => bail!("cannot read {} : {}", path, error),
+        };
+        match Selection::from_json(&content) {
+            Ok(selection) => Ok(selection),
+            Err(error) => bail!("{} is not a JSON result : {}", path, error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diff {
+    pub added: Vec<ShareId>,
+    pub removed: Vec<ShareId>,
+    // after minus before
+    pub cost: Decimal,
+    pub earnings: Decimal,
+    pub leftover: Decimal,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.cost.is_zero()
+            && self.earnings.is_zero()
+            && self.leftover.is_zero()
+    }
+}
+
+// The shares are compared as multisets, in case a result lists one twice
+pub fn diff(before: &Selection, after: &Selection) -> Diff {
+    let mut removed = before.shares.clone();
+    let mut added = Vec::new();
+    for share in &after.shares {
+        match removed.iter().position(|removed| removed == share) {
+            Some(i) => {
+                removed.remove(i);
+            }
+            None => added.push(share.clone()),
+        }
+    }
+    added.sort();
+    removed.sort();
+    Diff {
+        added,
+        removed,
+        cost: after.cost - before.cost,
+        earnings: after.earnings - before.earnings,
+        leftover: after.leftover - before.leftover,
+    }
+}
+
+pub fn shares_text(shares: &[ShareId]) -> String {
+    let shares: Vec<String> = shares.iter().map(|share| share.to_string()).collect();
+    shares.join(", ")
+}
+
+fn signed(value: Decimal) -> String {
+    if value.is_sign_negative() {
+        value.round_dp(2).to_string()
+    } else {
+        format!("+{}", value.round_dp(2))
+    }
+}
+
+pub fn write_diff(out: &mut dyn Write, diff: &Diff) -> io::Result<()> {
+    if diff.is_empty() {
+        return writeln!(out, "Same shares, same totals");
+    }
+    if diff.added.is_empty() && diff.removed.is_empty() {
+        writeln!(out, "Same shares")?;
+    }
+    if !diff.added.is_empty() {
+        writeln!(out, "Added : {}", shares_text(&diff.added))?;
+    }
+    if !diff.removed.is_empty() {
+        writeln!(out, "Removed : {}", shares_text(&diff.removed))?;
+    }
+    writeln!(
+        out,
+        "Cost {} ; earnings {} ; leftover {}",
+        signed(diff.cost),
+        signed(diff.earnings),
+        signed(diff.leftover)
+    )
+}
+
+// A solver by its number or its name
+pub fn parse_solver(solver: &str) -> Result<usize> {
+    if let Ok(algorithme) = solver.parse::<usize>() {
+        if algorithme < ALGORITHMES.len() {
+            return Ok(algorithme);
+        }
+    }
+    match ALGORITHMES.iter().position(|name| *name == solver) {
+        Some(algorithme) => Ok(algorithme),
+        None => bail!(
+            "no solver {}, expected 0 to {} or one of {}",
+            solver,
+            ALGORITHMES.len() - 1,
+            ALGORITHMES.join(", ")
+        ),
+    }
+}
+
+fn solve(args: &DiffArgs, cancellation: &Cancellation) -> Result<Vec<(String, Selection)>> {
+    let solvers = args
+        .solvers
+        .iter()
+        .map(|solver| parse_solver(solver))
+        .collect::<Result<Vec<usize>>>()?;
+    let balance: Decimal = args.dataset.balance.into();
+    let objective = args.objective.objective();
+    let (mut data, _) = load_dataset(&args.dataset.source)?;
+    sort_data(&mut data);
+    let mut selections = Vec::new();
+    for solver in solvers {
+        let search = Search::new(cancellation.with_time_limit(args.time_limit));
+        let best = run_algorithme(solver, data.clone(), balance, objective, &search)?;
+        let mut label = algorithme_name(solver).to_string();
+        if best.interrupted {
+            label.push_str(" (interrupted)");
+        }
+        selections.push((label, Selection::new(&best, balance)));
+    }
+    Ok(selections)
+}
+
+pub fn show_diff(args: &DiffArgs, cancellation: &Cancellation) -> Result<()> {
+    let selections = match (args.results.len(), args.solvers.len()) {
+        (2, 0) => args
+            .results
+            .iter()
+            .map(|path| Ok((path.clone(), Selection::read(path)?)))
+            .collect::<Result<Vec<(String, Selection)>>>()?,
+        (0, 2) => solve(args, cancellation)?,
+        _ => bail!("diff compares two JSON results, or two solvers given with --solvers"),
+    };
+    let [(before_label, before), (after_label, after)] = selections.as_slice() else {
+        unreachable!("two selections");
+    };
+    for (label, selection) in [(before_label, before), (after_label, after)] {
+        println!(
+            "{} : earnings {} ; spent {} ; leftover {} ; {} shares",
+            label,
+            selection.earnings.round_dp(2),
+            selection.cost,
+            selection.leftover,
+            selection.shares.len()
+        );
+    }
+    write_diff(&mut io::stdout(), &diff(before, after))?;
+    Ok(())
+}
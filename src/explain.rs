@@ -0,0 +1,159 @@
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use crate::{Best, Row};
+
+#[derive(Debug, Clone)]
+pub struct Swap {
+    // indexes of the chosen shares sold to make room for the rejected one
+    pub removed: Vec<usize>,
+    // earnings lost by the swap, negative when the swap improves the result
+    pub cost: Decimal,
+}
+
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    pub index: usize,
+    // rank by profit (%), 1 being the best
+    pub rank: usize,
+    pub benefit_per_euro: Decimal,
+    pub chosen: bool,
+    // whether the balance left when the share is considered (in rank order,
+    // after the chosen shares ranked before it) covers its price
+    pub affordable: bool,
+    // the cheapest swap which includes a rejected share, if the budget allows one
+    pub swap: Option<Swap>,
+}
+
+fn best_swap(data: &[Row], best: &Best, balance: Decimal, index: usize) -> Option<Swap> {
+    let row = &data[index];
+    if row.price > balance {
+        return None;
+    }
+    let needed = row.price - best.balance;
+    if needed <= zero!() {
+        return Some(Swap {
+            removed: Vec::new(),
+            cost: -row.benefits,
+        });
+    }
+    // Freeing the budget at the lowest earnings cost is a knapsack itself, so
+    // keep the better of two cheap candidates: the single chosen share that
+    // frees enough with the lowest benefits, or the chosen shares with the
+    // lowest benefit per euro sold until enough is freed.
+    let single = best
+        .actions
+        .iter()
+        .filter(|i| data[**i].price >= needed)
+        .min_by(|a, b| data[**a].benefits.cmp(&data[**b].benefits))
+        .map(|i| vec![*i]);
+    let mut by_ratio = best.actions.clone();
+    by_ratio.sort_by(|a, b| data[*a].profit.cmp(&data[*b].profit));
+    let mut freed = zero!();
+    let mut accumulated = Vec::new();
+    for i in by_ratio {
+        if freed >= needed {
+            break;
+        }
+        freed += data[i].price;
+        accumulated.push(i);
+    }
+    // the last share sold may free enough alone, keep only what is needed
+    accumulated.sort_by(|a, b| data[*b].benefits.cmp(&data[*a].benefits));
+    accumulated.retain(|i| {
+        if freed - data[*i].price >= needed {
+            freed -= data[*i].price;
+            false
+        } else {
+            true
+        }
+    });
+    let lost = |removed: &Vec<usize>| removed.iter().map(|i| data[*i].benefits).sum::<Decimal>();
+    let removed = match single {
+        Some(single) if lost(&single) <= lost(&accumulated) => single,
+        _ => accumulated,
+    };
+    Some(Swap {
+        cost: lost(&removed) - row.benefits,
+        removed,
+    })
+}
+
+pub fn explain(data: &[Row], best: &Best, balance: Decimal) -> Vec<Explanation> {
+    let mut explanations = Vec::new();
+    let mut available = balance;
+    for (index, row) in data.iter().enumerate() {
+        let chosen = best.actions.contains(&index);
+        let affordable = available >= row.price;
+        if chosen {
+            available -= row.price;
+        }
+        explanations.push(Explanation {
+            index,
+            rank: index + 1,
+            benefit_per_euro: row.benefits / row.price,
+            chosen,
+            affordable,
+            swap: if chosen {
+                None
+            } else {
+                best_swap(data, best, balance, index)
+            },
+        });
+    }
+    explanations
+}
+
+pub fn show_explanation(data: &[Row], explanations: &[Explanation]) {
+    println!("Why :");
+    for explanation in explanations {
+        let row = &data[explanation.index];
+        println!(
+            "#{} {} ({}% ; {:.4}€ per €) : {}{}",
+            explanation.rank,
+            row.name,
+            row.profit,
+            explanation.benefit_per_euro,
+            if explanation.chosen {
+                "bought"
+            } else {
+                "rejected"
+            },
+            if explanation.affordable {
+                ", affordable when considered"
+            } else {
+                ", out of budget when considered"
+            },
+        );
+        if explanation.chosen {
+            continue;
+        }
+        match &explanation.swap {
+            None => println!("    price {}€ above the whole balance", row.price),
+            Some(swap) if swap.removed.is_empty() => {
+                println!("    fits in the leftover, would add {}€", -swap.cost)
+            }
+            Some(swap) => {
+                let names: Vec<&str> = swap
+                    .removed
+                    .iter()
+                    .map(|i| data[*i].name.as_str())
+                    .collect();
+                if swap.cost < zero!() {
+                    println!(
+                        "    best swap : sell {} for it, would gain {}€",
+                        names.join(", "),
+                        -swap.cost
+                    );
+                } else {
+                    println!(
+                        "    best swap : sell {} for it, costs {}€ of earnings",
+                        names.join(", "),
+                        swap.cost
+                    );
+                }
+            }
+        }
+    }
+}
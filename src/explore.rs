@@ -0,0 +1,345 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use clap::Args;
+use rust_decimal::Decimal;
+
+use crate::cancel::{self, Cancellation};
+use crate::cli::{DatasetArgs, ObjectiveArgs};
+use crate::diff::{self, Selection};
+use crate::risk;
+use crate::search::Search;
+use crate::{
+    algorithme_name, load_dataset, run_algorithme, sort_data, Row, ALGORITHMES, EXACT_ALGORITHMES,
+};
+
+/// Load a dataset once, then change the balance, the solver or the shares and solve again
+#[derive(Args, Debug, Clone)]
+pub struct ExploreArgs {
+    #[clap(flatten)]
+    pub dataset: DatasetArgs,
+    #[clap(short, long, default_value_t = 0)]
+    pub algorithme: usize,
+    #[clap(flatten)]
+    pub objective: ObjectiveArgs,
+    /// Seconds given to each solve, the solver then returning its best result so far
+    #[clap(long, parse(try_from_str = cancel::parse_time_limit))]
+    pub time_limit: Option<Duration>,
+}
+
+const HELP: &str = "\
+balance <euros>        balance of the next solves
+solver <number>        solver of the next solves, listed by solvers
+lambda <λ>             maximise benefits − λ·risk (solvers 5 and 6)
+max-risk <R>           maximise benefits with a total risk ≤ R (solvers 5 and 6)
+benefits               maximise the benefits alone
+exclude <name>...      leave shares out of the next solves
+include <name>...      bring excluded shares back, all of them with include all
+time-limit <seconds>   time given to each solve, none for no limit
+solve                  solve, and compare with the previous result
+diff                   compare the last two results again
+show                   current settings
+solvers                list the solvers
+save <path>            write the transcript of the session
+help                   this help
+quit                   leave";
+
+// What a what-if exploration keeps between its commands
+pub struct Session {
+    // cleaned and sorted once for all the solves
+    data: Vec<Row>,
+    balance: Decimal,
+    algorithme: usize,
+    objective: ObjectiveArgs,
+    time_limit: Option<Duration>,
+    // a Ctrl-C interrupts the current solve, not the session
+    cancellation: Cancellation,
+    excluded: BTreeSet<String>,
+    results: Vec<Selection>,
+    transcript: String,
+}
+
+fn argument<'a>(arguments: &[&'a str], usage: &str) -> Result<&'a str> {
+    match arguments {
+        [argument] => Ok(argument),
+        _ => bail!("usage : {}", usage),
+    }
+}
+
+fn decimal(argument: &str) -> Result<Decimal> {
+    match Decimal::from_str(argument) {
+        Ok(value) if !value.is_sign_negative() => Ok(value),
+        _ => bail!("expected a positive number, got {}", argument),
+    }
+}
+
+impl Session {
+    pub fn new(
+        mut data: Vec<Row>,
+        balance: Decimal,
+        algorithme: usize,
+        objective: ObjectiveArgs,
+        time_limit: Option<Duration>,
+        cancellation: Cancellation,
+    ) -> Session {
+        sort_data(&mut data);
+        Session {
+            data,
+            balance,
+            algorithme,
+            objective,
+            time_limit,
+            cancellation,
+            excluded: BTreeSet::new(),
+            results: Vec::new(),
+            transcript: String::new(),
+        }
+    }
+
+    fn show_time_limit(&self, out: &mut dyn Write) -> Result<()> {
+        match self.time_limit {
+            Some(time_limit) => writeln!(out, "Time limit : {:?}", time_limit)?,
+            None => writeln!(out, "Time limit : none")?,
+        }
+        Ok(())
+    }
+
+    fn show(&self, out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "Balance : {}", self.balance)?;
+        writeln!(
+            out,
+            "Solver : {} {}",
+            self.algorithme,
+            algorithme_name(self.algorithme)
+        )?;
+        writeln!(out, "Objective : {}", self.objective.objective())?;
+        self.show_time_limit(out)?;
+        let excluded: Vec<&str> = self.excluded.iter().map(|name| name.as_str()).collect();
+        writeln!(
+            out,
+            "Excluded : {}",
+            if excluded.is_empty() {
+                "none".to_string()
+            } else {
+                excluded.join(", ")
+            }
+        )?;
+        Ok(())
+    }
+
+    fn solve(&mut self, out: &mut dyn Write) -> Result<()> {
+        let data: Vec<Row> = self
+            .data
+            .iter()
+            .filter(|row| !self.excluded.contains(&row.name))
+            .cloned()
+            .collect();
+        let search = Search::new(self.cancellation.with_time_limit(self.time_limit));
+        let start = Instant::now();
+        let best = run_algorithme(
+            self.algorithme,
+            data.clone(),
+            self.balance,
+            self.objective.objective(),
+            &search,
+        );
+        self.cancellation.reset();
+        let best = best?;
+        let duration = start.elapsed();
+        let selection = Selection::new(&best, self.balance);
+        writeln!(
+            out,
+            "{} in {:?}{}",
+            algorithme_name(self.algorithme),
+            duration,
+            if best.interrupted {
+                ", interrupted : best result found so far, not proven optimal"
+            } else {
+                ""
+            }
+        )?;
+        writeln!(
+            out,
+            "Earnings : {} ; spent : {} ; leftover : {} ; {} shares",
+            selection.earnings.round_dp(2),
+            selection.cost,
+            selection.leftover,
+            selection.shares.len()
+        )?;
+        writeln!(out, "Shares : {}", diff::shares_text(&selection.shares))?;
+        if let Some(previous) = self.results.last() {
+            writeln!(out, "Compared with the previous result :")?;
+            diff::write_diff(out, &diff::diff(previous, &selection))?;
+        }
+        self.results.push(selection);
+        Ok(())
+    }
+
+    // Runs a line of the user, false once the session is over
+    pub fn execute(&mut self, line: &str, out: &mut dyn Write) -> Result<bool> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((command, arguments)) = words.split_first() else {
+            return Ok(true);
+        };
+        match *command {
+            "balance" => {
+                self.balance = decimal(argument(arguments, "balance <euros>")?)?;
+                writeln!(out, "Balance : {}", self.balance)?;
+            }
+            "solver" | "algorithme" => {
+                let algorithme = argument(arguments, "solver <number>")?;
+                match algorithme.parse() {
+                    Ok(algorithme) if algorithme < ALGORITHMES.len() => {
+                        self.algorithme = algorithme;
+                    }
+                    _ => bail!("no solver {}, solvers lists them", algorithme),
+                }
+                writeln!(
+                    out,
+                    "Solver : {} {}",
+                    self.algorithme,
+                    algorithme_name(self.algorithme)
+                )?;
+            }
+            "lambda" => {
+                self.objective.lambda =
+                    Some(risk::parse_lambda(argument(arguments, "lambda <λ>")?)?);
+                self.objective.max_risk = None;
+                writeln!(out, "Objective : {}", self.objective.objective())?;
+            }
+            "max-risk" => {
+                self.objective.max_risk =
+                    Some(risk::parse_max_risk(argument(arguments, "max-risk <R>")?)?);
+                self.objective.lambda = None;
+                writeln!(out, "Objective : {}", self.objective.objective())?;
+            }
+            "benefits" => {
+                self.objective.lambda = None;
+                self.objective.max_risk = None;
+                writeln!(out, "Objective : {}", self.objective.objective())?;
+            }
+            "exclude" => {
+                if arguments.is_empty() {
+                    bail!("usage : exclude <name>...");
+                }
+                let unknown: Vec<&str> = arguments
+                    .iter()
+                    .filter(|name| !self.data.iter().any(|row| row.name == **name))
+                    .copied()
+                    .collect();
+                if !unknown.is_empty() {
+                    bail!("no share {} in the dataset", unknown.join(", "));
+                }
+                self.excluded
+                    .extend(arguments.iter().map(|name| name.to_string()));
+                writeln!(out, "{} shares excluded", self.excluded.len())?;
+            }
+            "include" => {
+                match arguments {
+                    [] => bail!("usage : include <name>... or include all"),
+                    ["all"] => self.excluded.clear(),
+                    names => {
+                        for name in names {
+                            if !self.excluded.remove(*name) {
+                                bail!("{} is not excluded", name);
+                            }
+                        }
+                    }
+                }
+                writeln!(out, "{} shares excluded", self.excluded.len())?;
+            }
+            "time-limit" => {
+                self.time_limit = match argument(arguments, "time-limit <seconds>")? {
+                    "none" => None,
+                    seconds => Some(cancel::parse_time_limit(seconds)?),
+                };
+                self.show_time_limit(out)?;
+            }
+            "solve" => self.solve(out)?,
+            "diff" => match self.results.as_slice() {
+                [.., previous, last] => diff::write_diff(out, &diff::diff(previous, last))?,
+                _ => bail!("two results are needed, solve twice"),
+            },
+            "show" => self.show(out)?,
+            "solvers" => {
+                for (algorithme, name) in ALGORITHMES.iter().enumerate() {
+                    let exact = if EXACT_ALGORITHMES.contains(&algorithme) {
+                        "exact"
+                    } else {
+                        "heuristic"
+                    };
+                    writeln!(out, "{} {} ({})", algorithme, name, exact)?;
+                }
+            }
+            "save" => {
+                let path = argument(arguments, "save <path>")?;
+                fs::write(path, &self.transcript)?;
+                writeln!(out, "Transcript written to {}", path)?;
+            }
+            "help" => writeln!(out, "{}", HELP)?,
+            "quit" | "exit" => return Ok(false),
+            command => bail!("unknown command {}, help lists them", command),
+        }
+        Ok(true)
+    }
+
+    // Reads the commands until the end of the input or quit, the errors
+    // being shown without ending the session
+    pub fn run(&mut self, input: impl BufRead, out: &mut dyn Write, prompt: bool) -> Result<()> {
+        if prompt {
+            write!(out, "> ")?;
+            out.flush()?;
+        }
+        for line in input.lines() {
+            let line = line?;
+            // the transcript of a command goes up to the save it holds
+            self.transcript.push_str(&format!("> {}\n", line));
+            let mut output = Vec::new();
+            let keep_going = match self.execute(&line, &mut output) {
+                Ok(keep_going) => keep_going,
+                Err(error) => {
+                    writeln!(output, "Error : {}", error)?;
+                    true
+                }
+            };
+            self.transcript.push_str(&String::from_utf8_lossy(&output));
+            out.write_all(&output)?;
+            if !keep_going {
+                break;
+            }
+            if prompt {
+                write!(out, "> ")?;
+                out.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn explore(args: &ExploreArgs, cancellation: &Cancellation) -> Result<()> {
+    if args.algorithme >= ALGORITHMES.len() {
+        bail!("algorithme number {} does not exist.", args.algorithme);
+    }
+    let source = &args.dataset.source;
+    let (data, cleaning) = load_dataset(source)?;
+    println!(
+        "{} shares loaded from {}, help lists the commands",
+        cleaning.kept,
+        source.path()
+    );
+    let mut session = Session::new(
+        data,
+        args.dataset.balance.into(),
+        args.algorithme,
+        args.objective.clone(),
+        args.time_limit,
+        cancellation.clone(),
+    );
+    let stdin = io::stdin();
+    let prompt = stdin.is_terminal();
+    session.run(stdin.lock(), &mut io::stdout(), prompt)
+}
@@ -0,0 +1,131 @@
+use crate::algorithme_name;
+use crate::benchmark::Measure;
+use crate::curves::Reference;
+
+pub const MODELS: [Reference; 5] = [
+    Reference::Linear,
+    Reference::Linearithmic,
+    Reference::Quadratic,
+    Reference::PseudoPolynomial,
+    Reference::Exponential,
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct Fit {
+    pub model: Reference,
+    // duration = k·f(n) + c, with f divided by its largest value over the
+    // measures (ln_scale being its logarithm) so that 2^n does not overflow
+    k: f64,
+    c: f64,
+    ln_scale: f64,
+    balance: f64,
+    pub r2: f64,
+}
+
+impl Fit {
+    pub fn predict(&self, n: usize) -> f64 {
+        let f = (self.model.ln_complexity(n as f64, self.balance) - self.ln_scale).exp();
+        self.k * f + self.c
+    }
+
+    // the constant of the complexity, 0 when it is too small for a f64
+    pub fn constant(&self) -> f64 {
+        self.k * (-self.ln_scale).exp()
+    }
+
+    pub fn label(&self) -> String {
+        format!(
+            "{} with k = {:.3e} (R² = {:.3})",
+            self.model.label(),
+            self.constant(),
+            self.r2
+        )
+    }
+}
+
+// Least squares of duration = k·f(n) + c over the (n, duration) points
+pub fn fit(model: Reference, points: &[(usize, f64)], balance: f64) -> Option<Fit> {
+    if points.len() < 2 {
+        return None;
+    }
+    let ln_f: Vec<f64> = points
+        .iter()
+        .map(|(n, _)| model.ln_complexity(*n as f64, balance))
+        .collect();
+    let ln_scale = ln_f.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if !ln_scale.is_finite() {
+        return None;
+    }
+    let f: Vec<f64> = ln_f.iter().map(|ln| (ln - ln_scale).exp()).collect();
+    let count = points.len() as f64;
+    let mean_f = f.iter().sum::<f64>() / count;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let mut covariance = 0.0;
+    let mut variance = 0.0;
+    for (fi, (_, y)) in f.iter().zip(points) {
+        covariance += (fi - mean_f) * (y - mean_y);
+        variance += (fi - mean_f).powi(2);
+    }
+    if variance <= 0.0 {
+        return None;
+    }
+    let k = covariance / variance;
+    let c = mean_y - k * mean_f;
+    let mut residuals = 0.0;
+    let mut total = 0.0;
+    for (fi, (_, y)) in f.iter().zip(points) {
+        residuals += (y - (k * fi + c)).powi(2);
+        total += (y - mean_y).powi(2);
+    }
+    let r2 = if total > 0.0 {
+        1.0 - residuals / total
+    } else {
+        1.0
+    };
+    Some(Fit {
+        model,
+        k,
+        c,
+        ln_scale,
+        balance,
+        r2,
+    })
+}
+
+// Every model fitted on the points, the best one (highest R²) first. With a
+// single balance n·W is n up to a constant, so equal R² keep the order of
+// MODELS, the simplest model first.
+pub fn fit_all(points: &[(usize, f64)], balance: f64) -> Vec<Fit> {
+    let mut fits: Vec<Fit> = MODELS
+        .iter()
+        .filter_map(|model| fit(*model, points, balance))
+        .collect();
+    let rounded = |fit: &Fit| (fit.r2 * 1e9).round();
+    fits.sort_by(|a, b| rounded(b).total_cmp(&rounded(a)));
+    fits
+}
+
+pub fn show_fits(solvers: &[usize], measures: &[Measure], balance: f64) {
+    for solver in solvers {
+        let points: Vec<(usize, f64)> = measures
+            .iter()
+            .filter(|measure| measure.solver == *solver)
+            .map(|measure| (measure.size, measure.median_ns as f64 / 1_000_000.0))
+            .collect();
+        let fits = fit_all(&points, balance);
+        match fits.first() {
+            Some(best) => println!(
+                "Complexity of {} : {}",
+                algorithme_name(*solver),
+                best.label()
+            ),
+            None => println!(
+                "Complexity of {} : not enough measures to fit",
+                algorithme_name(*solver)
+            ),
+        }
+        for fit in fits.iter().skip(1) {
+            println!("    {}", fit.label());
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::fs::File;
+
+use anyhow::{bail, Result};
+use clap::{ArgEnum, Args};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::StandardNormal;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distribution {
+    Uniform,
+    Normal,
+    #[clap(name = "lognormal")]
+    LogNormal,
+}
+
+// Classes of instances, the hard ones being known to slow the knapsack
+// solvers down since every share is about as interesting as the others
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    // the profits follow their own distribution
+    Uncorrelated,
+    // the benefits are proportional to the price, plus a noise
+    #[clap(name = "weakly-correlated")]
+    WeaklyCorrelated,
+    // the benefits are proportional to the price, plus a constant
+    #[clap(name = "strongly-correlated")]
+    StronglyCorrelated,
+    // the prices are proportional to the benefits, plus a constant
+    #[clap(name = "inverse-strongly-correlated")]
+    InverseStronglyCorrelated,
+    // every share has the same profit, only filling the budget matters
+    #[clap(name = "subset-sum")]
+    SubsetSum,
+}
+
+/// Write a synthetic dataset in the `name,price,profit` format
+#[derive(Args, Debug)]
+pub struct GenerateArgs {
+    /// CSV file written, dataset/dataset<N>_Python+P7.csv to load it with --dataset N
+    output: String,
+    /// Number of rows
+    #[clap(long, default_value_t = 1000)]
+    rows: usize,
+    #[clap(long, arg_enum, default_value = "uniform")]
+    price_distribution: Distribution,
+    #[clap(long, default_value = "50")]
+    price_mean: f64,
+    #[clap(long, default_value = "25")]
+    price_stddev: f64,
+    /// Distribution of the profits, of the uncorrelated class only
+    #[clap(long, arg_enum, default_value = "uniform")]
+    profit_distribution: Distribution,
+    /// Profit in percent of the price
+    #[clap(long, default_value = "20")]
+    profit_mean: f64,
+    #[clap(long, default_value = "10")]
+    profit_stddev: f64,
+    /// Correlation between the price and the profit, in [-1, 1], of the uncorrelated class only
+    #[clap(long, default_value = "0", allow_hyphen_values = true)]
+    correlation: f64,
+    /// Fraction of rows with a price or a profit not positive, removed by the cleaning
+    #[clap(long, default_value = "0")]
+    bad_rows: f64,
+    #[clap(long, arg_enum, default_value = "uncorrelated")]
+    class: Class,
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct GeneratedRow {
+    name: String,
+    price: Decimal,
+    profit: Decimal,
+}
+
+// Cumulative distribution function of the standard normal distribution
+// (Abramowitz and Stegun 7.1.26, accurate to 1.5e-7)
+fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / 2f64.sqrt();
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let polynomial = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - polynomial * (-x * x).exp();
+    if z >= 0.0 {
+        (1.0 + erf) / 2.0
+    } else {
+        (1.0 - erf) / 2.0
+    }
+}
+
+impl Distribution {
+    // The value of the distribution of the given mean and standard deviation
+    // at the quantile of `z`, a standard normal sample, so that correlated
+    // samples stay correlated whatever the distribution
+    fn value(&self, z: f64, mean: f64, stddev: f64) -> f64 {
+        match self {
+            Distribution::Uniform => {
+                let half_width = stddev * 3f64.sqrt();
+                mean - half_width + 2.0 * half_width * normal_cdf(z)
+            }
+            Distribution::Normal => mean + stddev * z,
+            Distribution::LogNormal => {
+                let sigma2 = (1.0 + (stddev / mean).powi(2)).ln();
+                let mu = mean.ln() - sigma2 / 2.0;
+                (mu + sigma2.sqrt() * z).exp()
+            }
+        }
+    }
+}
+
+// Unique names in the style of the datasets, Share-AAAA, Share-AAAB...
+fn share_name(mut index: usize) -> String {
+    let mut letters = Vec::new();
+    while letters.len() < 4 || index > 0 {
+        letters.push((b'A' + (index % 26) as u8) as char);
+        index /= 26;
+    }
+    letters.reverse();
+    format!("Share-{}", letters.into_iter().collect::<String>())
+}
+
+fn round(value: f64) -> Result<Decimal> {
+    match Decimal::from_f64(value) {
+        Some(value) => Ok(value.round_dp(2)),
+        None => bail!("generated value {} is not a decimal", value),
+    }
+}
+
+impl GenerateArgs {
+    fn check(&self) -> Result<()> {
+        if !(-1.0..=1.0).contains(&self.correlation) {
+            bail!(
+                "the correlation must be in [-1, 1], got {}",
+                self.correlation
+            );
+        }
+        if !(0.0..=1.0).contains(&self.bad_rows) {
+            bail!(
+                "the fraction of bad rows must be in [0, 1], got {}",
+                self.bad_rows
+            );
+        }
+        // the other classes derive the benefits from the price
+        if self.class != Class::Uncorrelated
+            && (self.correlation != 0.0 || self.profit_distribution != Distribution::Uniform)
+        {
+            bail!("--correlation and --profit-distribution only apply to the uncorrelated class");
+        }
+        if self.price_mean <= 0.0 || self.profit_mean <= 0.0 {
+            bail!("the means of the price and the profit must be positive");
+        }
+        if self.price_stddev < 0.0 || self.profit_stddev < 0.0 {
+            bail!("the standard deviations must not be negative");
+        }
+        Ok(())
+    }
+
+    // A valid (price, profit) pair, prices and benefits of at least a cent
+    fn sample(&self, rng: &mut ChaCha8Rng) -> (f64, f64) {
+        let z_price: f64 = rng.sample(StandardNormal);
+        let noise: f64 = rng.sample(StandardNormal);
+        let z_profit = self.correlation * z_price + (1.0 - self.correlation.powi(2)).sqrt() * noise;
+        let price = self
+            .price_distribution
+            .value(z_price, self.price_mean, self.price_stddev)
+            .max(0.01);
+        let profit = self
+            .profit_distribution
+            .value(z_profit, self.profit_mean, self.profit_stddev);
+
+        // the classes are defined on the benefits, which are turned back into
+        // a profit in percent of the price; the largest price stands for the
+        // range of the weights of the usual definitions
+        let range = self.price_mean + 3.0 * self.price_stddev;
+        let rate = self.profit_mean / 100.0;
+        let (price, benefits) = match self.class {
+            Class::Uncorrelated => (price, price * profit / 100.0),
+            Class::WeaklyCorrelated => {
+                let spread = range * rate / 10.0;
+                (price, price * rate + rng.gen_range(-spread..=spread))
+            }
+            Class::StronglyCorrelated => (price, price * rate + range * rate / 10.0),
+            Class::InverseStronglyCorrelated => {
+                let benefits = price * rate;
+                (price + range / 10.0, benefits)
+            }
+            Class::SubsetSum => (price, price * rate),
+        };
+        let benefits = benefits.max(price * 0.0001).max(0.0001);
+        (price, (benefits / price * 100.0).max(0.01))
+    }
+
+    // A row which the cleaning of the datasets removes
+    fn bad_sample(&self, rng: &mut ChaCha8Rng) -> (f64, f64) {
+        let (price, profit) = self.sample(rng);
+        match rng.gen_range(0..3) {
+            0 => (-price, profit),
+            1 => (0.0, profit),
+            _ => (price, -profit),
+        }
+    }
+}
+
+pub fn generate(args: &GenerateArgs) -> Result<()> {
+    args.check()?;
+    let mut rng = ChaCha8Rng::seed_from_u64(args.seed);
+    let file = File::create(&args.output)?;
+    let mut writer = csv::Writer::from_writer(file);
+    let mut bad_rows = 0;
+    for index in 0..args.rows {
+        let (price, profit) = if rng.gen_bool(args.bad_rows) {
+            bad_rows += 1;
+            args.bad_sample(&mut rng)
+        } else {
+            args.sample(&mut rng)
+        };
+        writer.serialize(GeneratedRow {
+            name: share_name(index),
+            price: round(price)?,
+            profit: round(profit)?,
+        })?;
+    }
+    writer.flush()?;
+    println!(
+        "{} rows ({} bad) written to {}",
+        args.rows, bad_rows, args.output
+    );
+    Ok(())
+}
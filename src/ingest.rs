@@ -0,0 +1,625 @@
+// Reading of the datasets. CSV files are read as spreadsheets export them:
+// any delimiter among , ; tab and |, a UTF-8 or UTF-16 BOM, decimal commas,
+// quoted numbers and headers in any order or case. JSON arrays, JSON Lines and
+// TOML files hold the same fields, and the fields of no column are kept as
+// metadata. Every invalid field is reported with its place rather than
+// stopping at the first one.
+//
+// The module only depends on anyhow, csv, rust_decimal, serde_json and toml,
+// so that the fuzz target (fuzz/fuzz_targets/ingest.rs) can include it as is.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use rust_decimal::Decimal;
+
+#[derive(Debug, Clone)]
+pub struct RowBrut {
+    pub name: String,
+    pub price: Decimal,
+    pub profit: Decimal,
+    pub profit_min: Option<Decimal>,
+    pub profit_max: Option<Decimal>,
+    pub risk: Decimal,
+    // fields besides the ones above, carried to the outputs
+    pub metadata: BTreeMap<String, String>,
+    // line of the row in its file, or rank of its record in a JSON array or a
+    // TOML file, which identifies the share with its name
+    pub line: usize,
+}
+
+// The rule broken by a record that could not be read as a share
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    InvalidNumber,
+    OutOfRange,
+    MissingField,
+    DuplicatedField,
+    // more fields than the header, in a CSV file
+    ExtraFields,
+    // not a record at all : broken quotes, invalid JSON, not an object
+    Unreadable,
+}
+
+// The errors of a record, each with the rule it breaks
+type Errors = Vec<(Rule, String)>;
+
+// A record that could not be read as a share, with every error it holds
+#[derive(Debug, Clone)]
+pub struct Invalid {
+    // line N, or share N of a JSON array or a TOML file
+    pub place: String,
+    pub errors: Errors,
+}
+
+// in the order preferred when a header holds as many of several of them
+const DELIMITERS: [u8; 4] = [b',', b';', b'\t', b'|'];
+
+// Larger prices or profits would overflow the decimals once multiplied and
+// summed by the solvers
+const LIMIT: i64 = 1_000_000_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Name,
+    Price,
+    Profit,
+    ProfitMin,
+    ProfitMax,
+    Risk,
+}
+
+const REQUIRED: [Column; 3] = [Column::Name, Column::Price, Column::Profit];
+
+impl Column {
+    fn from_header(header: &str) -> Option<Column> {
+        let header = header.trim().to_lowercase().replace(['-', ' '], "_");
+        match header.as_str() {
+            "name" => Some(Column::Name),
+            "price" => Some(Column::Price),
+            "profit" => Some(Column::Profit),
+            "profit_min" => Some(Column::ProfitMin),
+            "profit_max" => Some(Column::ProfitMax),
+            "risk" => Some(Column::Risk),
+            _ => None,
+        }
+    }
+
+    fn header(self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::Price => "price",
+            Column::Profit => "profit",
+            Column::ProfitMin => "profit_min",
+            Column::ProfitMax => "profit_max",
+            Column::Risk => "risk",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json,
+    Jsonl,
+    Toml,
+}
+
+impl Format {
+    pub fn from_path(path: &str) -> Option<Format> {
+        let (_, extension) = path.rsplit_once('.')?;
+        match extension.to_lowercase().as_str() {
+            "csv" | "tsv" | "txt" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            "jsonl" | "ndjson" => Some(Format::Jsonl),
+            "toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+            Format::Jsonl => "jsonl",
+            Format::Toml => "toml",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> Result<Format> {
+        match Format::from_path(&format!(".{}", format)) {
+            Some(format) => Ok(format),
+            None => bail!(
+                "unknown format {}, expected csv, json, jsonl or toml",
+                format
+            ),
+        }
+    }
+}
+
+pub fn read_dataset(path: &str, format: Format) -> Result<Vec<RowBrut>> {
+    let content = match fs::read(path) {
+        Ok(content) => content,
+        Err(error) => bail!("cannot read the dataset {} : {}", path, error),
+    };
+    match parse(&content, format) {
+        Ok(rows) => Ok(rows),
+        Err(error) => bail!("invalid dataset {} : {}", path, error),
+    }
+}
+
+// The valid rows of a dataset and the records that are not, so that the
+// valid ones can still be described. Only an unreadable file or header is an
+// error.
+pub fn read_records(path: &str, format: Format) -> Result<(Vec<RowBrut>, Vec<Invalid>)> {
+    let content = match fs::read(path) {
+        Ok(content) => content,
+        Err(error) => bail!("cannot read the dataset {} : {}", path, error),
+    };
+    match parse_records(&content, format) {
+        Ok(records) => Ok(records),
+        Err(error) => bail!("invalid dataset {} : {}", path, error),
+    }
+}
+
+pub fn parse(content: &[u8], format: Format) -> Result<Vec<RowBrut>> {
+    match format {
+        Format::Csv => parse_csv(content),
+        Format::Json => parse_json(content),
+        Format::Jsonl => parse_jsonl(content),
+        Format::Toml => parse_toml(content),
+    }
+}
+
+pub fn parse_records(content: &[u8], format: Format) -> Result<(Vec<RowBrut>, Vec<Invalid>)> {
+    match format {
+        Format::Csv => csv_records(content),
+        Format::Json => json_file_records(content),
+        Format::Jsonl => jsonl_records(content),
+        Format::Toml => toml_records(content),
+    }
+}
+
+pub fn parse_csv(content: &[u8]) -> Result<Vec<RowBrut>> {
+    let (rows, invalid) = csv_records(content)?;
+    rows_or_errors(rows, &invalid)
+}
+
+fn csv_records(content: &[u8]) -> Result<(Vec<RowBrut>, Vec<Invalid>)> {
+    let text = decode(content)?;
+    let delimiter = detect_delimiter(&text);
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let mut records = reader.records();
+
+    let header = match records.next() {
+        Some(Ok(header)) => header,
+        Some(Err(error)) => bail!("unreadable header : {}", error),
+        None => bail!("empty file, expected a name,price,profit header"),
+    };
+    check_header(&header)?;
+    let headers: Vec<String> = header.iter().map(|field| field.to_string()).collect();
+
+    // the csv crate does not count the blank lines, and starts a record at the
+    // blank lines before it, so the line of a record is counted up to its
+    // first byte after them
+    let (mut offset, mut line) = (0, 1);
+    let mut line_at = |byte: u64| -> usize {
+        let mut byte = (byte as usize).clamp(offset, text.len());
+        while matches!(text.as_bytes().get(byte), Some(b'\r' | b'\n')) {
+            byte += 1;
+        }
+        line += text.as_bytes()[offset..byte]
+            .iter()
+            .filter(|byte| **byte == b'\n')
+            .count();
+        offset = byte;
+        line
+    };
+
+    let mut rows = Vec::new();
+    let mut invalid = Vec::new();
+    for record in records {
+        let record = match record {
+            Ok(record) => record,
+            Err(error) => {
+                let line = error.position().map(|position| line_at(position.byte()));
+                invalid.push(Invalid {
+                    place: format!("line {}", line.unwrap_or_default()),
+                    errors: vec![(Rule::Unreadable, error.to_string())],
+                });
+                continue;
+            }
+        };
+        let line = record.position().map(|position| line_at(position.byte()));
+        let line = line.unwrap_or_default();
+        // lines of delimiters only, left by spreadsheets
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        // trailing empty fields are left by some exports
+        let extra = record
+            .iter()
+            .skip(headers.len())
+            .filter(|field| !field.trim().is_empty())
+            .count();
+        if extra > 0 {
+            invalid.push(Invalid {
+                place: format!("line {}", line),
+                errors: vec![(
+                    Rule::ExtraFields,
+                    format!(
+                        "{} fields, the header has {}",
+                        headers.len() + extra,
+                        headers.len()
+                    ),
+                )],
+            });
+            continue;
+        }
+        let fields: Vec<(String, String)> = headers
+            .iter()
+            .cloned()
+            .zip(record.iter().map(|field| field.to_string()))
+            .collect();
+        match parse_row(&fields, line) {
+            Ok(row) => rows.push(row),
+            Err(errors) => invalid.push(Invalid {
+                place: format!("line {}", line),
+                errors,
+            }),
+        }
+    }
+    Ok((rows, invalid))
+}
+
+// A JSON array of objects, one per share
+pub fn parse_json(content: &[u8]) -> Result<Vec<RowBrut>> {
+    let (rows, invalid) = json_file_records(content)?;
+    rows_or_errors(rows, &invalid)
+}
+
+fn json_file_records(content: &[u8]) -> Result<(Vec<RowBrut>, Vec<Invalid>)> {
+    let text = decode(content)?;
+    let records = match serde_json::from_str(&text) {
+        Ok(serde_json::Value::Array(records)) => records,
+        Ok(_) => bail!("expected an array of shares"),
+        Err(error) => bail!("{}", error),
+    };
+    Ok(json_records(&records))
+}
+
+// The shares of JSON objects already parsed, as the HTTP API receives them
+pub fn parse_json_records(records: &[serde_json::Value]) -> Result<Vec<RowBrut>> {
+    let (rows, invalid) = json_records(records);
+    rows_or_errors(rows, &invalid)
+}
+
+// The valid shares and the invalid ones, which validate counts
+pub fn json_records(records: &[serde_json::Value]) -> (Vec<RowBrut>, Vec<Invalid>) {
+    let mut rows = Vec::new();
+    let mut invalid = Vec::new();
+    for (i, record) in records.iter().enumerate() {
+        match json_fields(record).and_then(|fields| parse_row(&fields, i + 1)) {
+            Ok(row) => rows.push(row),
+            Err(errors) => invalid.push(Invalid {
+                place: format!("share {}", i + 1),
+                errors,
+            }),
+        }
+    }
+    (rows, invalid)
+}
+
+// JSON Lines, an object per line
+pub fn parse_jsonl(content: &[u8]) -> Result<Vec<RowBrut>> {
+    let (rows, invalid) = jsonl_records(content)?;
+    rows_or_errors(rows, &invalid)
+}
+
+fn jsonl_records(content: &[u8]) -> Result<(Vec<RowBrut>, Vec<Invalid>)> {
+    let text = decode(content)?;
+    let mut rows = Vec::new();
+    let mut invalid = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = match serde_json::from_str(line) {
+            Ok(record) => json_fields(&record),
+            Err(error) => Err(vec![(Rule::Unreadable, error.to_string())]),
+        };
+        match fields.and_then(|fields| parse_row(&fields, i + 1)) {
+            Ok(row) => rows.push(row),
+            Err(errors) => invalid.push(Invalid {
+                place: format!("line {}", i + 1),
+                errors,
+            }),
+        }
+    }
+    Ok((rows, invalid))
+}
+
+fn json_fields(record: &serde_json::Value) -> Result<Vec<(String, String)>, Errors> {
+    let serde_json::Value::Object(record) = record else {
+        return Err(vec![(Rule::Unreadable, "expected an object".to_string())]);
+    };
+    Ok(record
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                serde_json::Value::Null => String::new(),
+                serde_json::Value::String(text) => text.clone(),
+                value => value.to_string(),
+            };
+            (key.clone(), value)
+        })
+        .collect())
+}
+
+// Tables of an array named share, for the small universes written by hand
+//
+// [[share]]
+// name = "Share-A"
+// price = 20
+// profit = 5
+pub fn parse_toml(content: &[u8]) -> Result<Vec<RowBrut>> {
+    let (rows, invalid) = toml_records(content)?;
+    rows_or_errors(rows, &invalid)
+}
+
+fn toml_records(content: &[u8]) -> Result<(Vec<RowBrut>, Vec<Invalid>)> {
+    let text = decode(content)?;
+    let mut file: toml::Table = match text.parse() {
+        Ok(file) => file,
+        Err(error) => bail!("{}", error),
+    };
+    let records = match file.remove("share") {
+        Some(toml::Value::Array(records)) => records,
+        Some(_) => bail!("share must be an array of tables, written [[share]]"),
+        None => Vec::new(),
+    };
+    if let Some(key) = file.keys().next() {
+        bail!("unknown key {}, the shares are [[share]] tables", key);
+    }
+    let mut rows = Vec::new();
+    let mut invalid = Vec::new();
+    for (i, record) in records.iter().enumerate() {
+        let fields = match record {
+            toml::Value::Table(record) => Ok(record
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        toml::Value::String(text) => text.clone(),
+                        value => value.to_string(),
+                    };
+                    (key.clone(), value)
+                })
+                .collect::<Vec<_>>()),
+            _ => Err(vec![(Rule::Unreadable, "expected a table".to_string())]),
+        };
+        match fields.and_then(|fields| parse_row(&fields, i + 1)) {
+            Ok(row) => rows.push(row),
+            Err(errors) => invalid.push(Invalid {
+                place: format!("share {}", i + 1),
+                errors,
+            }),
+        }
+    }
+    Ok((rows, invalid))
+}
+
+// The text without its BOM, from UTF-8 or UTF-16
+fn decode(content: &[u8]) -> Result<String> {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| -> Result<String> {
+        if !bytes.len().is_multiple_of(2) {
+            bail!("UTF-16 text with an odd number of bytes");
+        }
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| from_bytes([pair[0], pair[1]]))
+            .collect();
+        match String::from_utf16(&units) {
+            Ok(text) => Ok(text),
+            Err(_) => bail!("invalid UTF-16 text"),
+        }
+    };
+    match content {
+        [0xef, 0xbb, 0xbf, rest @ ..] => utf8(rest),
+        [0xff, 0xfe, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xfe, 0xff, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        _ => utf8(content),
+    }
+}
+
+fn utf8(content: &[u8]) -> Result<String> {
+    match String::from_utf8(content.to_vec()) {
+        Ok(text) => Ok(text),
+        Err(error) => {
+            let valid = &content[..error.utf8_error().valid_up_to()];
+            let line = valid.iter().filter(|byte| **byte == b'\n').count() + 1;
+            bail!("line {} : not UTF-8 text", line)
+        }
+    }
+}
+
+// The delimiter found the most often in the header, outside of quotes
+fn detect_delimiter(text: &str) -> u8 {
+    let header = text
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or_default();
+    let mut counts = [0; DELIMITERS.len()];
+    let mut quoted = false;
+    for byte in header.bytes() {
+        if byte == b'"' {
+            quoted = !quoted;
+        } else if !quoted {
+            if let Some(i) = DELIMITERS.iter().position(|delimiter| *delimiter == byte) {
+                counts[i] += 1;
+            }
+        }
+    }
+    let mut best = 0;
+    for i in 1..DELIMITERS.len() {
+        if counts[i] > counts[best] {
+            best = i;
+        }
+    }
+    DELIMITERS[best]
+}
+
+// The known columns, each given once, the other ones holding metadata
+fn check_header(header: &csv::StringRecord) -> Result<()> {
+    let columns: Vec<Option<Column>> = header.iter().map(Column::from_header).collect();
+    let mut errors = Vec::new();
+    for (i, column) in columns.iter().enumerate() {
+        if let Some(column) = column {
+            if columns[..i].contains(&Some(*column)) {
+                errors.push(format!("duplicated column {}", column.header()));
+            }
+        }
+    }
+    for column in REQUIRED {
+        if !columns.contains(&Some(column)) {
+            errors.push(format!("missing column {}", column.header()));
+        }
+    }
+    if !errors.is_empty() {
+        let found: Vec<&str> = header.iter().map(|field| field.trim()).collect();
+        let line = header.position().map(|position| position.line());
+        bail!(
+            "line {} : {} (the header is {})",
+            line.unwrap_or(1),
+            errors.join(", "),
+            found.join(", ")
+        );
+    }
+    Ok(())
+}
+
+// A share from its fields, named by the header of their column or by their
+// key in a JSON or TOML record, the unknown ones being kept as metadata
+fn parse_row(fields: &[(String, String)], line: usize) -> Result<RowBrut, Errors> {
+    let mut errors = Vec::new();
+    let mut row = RowBrut {
+        name: String::new(),
+        price: Decimal::ZERO,
+        profit: Decimal::ZERO,
+        profit_min: None,
+        profit_max: None,
+        risk: Decimal::ZERO,
+        metadata: BTreeMap::new(),
+        line,
+    };
+    let mut found = Vec::new();
+    for (key, field) in fields {
+        let field = field.trim();
+        let Some(column) = Column::from_header(key) else {
+            if !field.is_empty() {
+                row.metadata
+                    .insert(key.trim().to_string(), field.to_string());
+            }
+            continue;
+        };
+        if field.is_empty() {
+            continue;
+        }
+        if found.contains(&column) {
+            errors.push((
+                Rule::DuplicatedField,
+                format!("duplicated {}", column.header()),
+            ));
+            continue;
+        }
+        found.push(column);
+        if column == Column::Name {
+            row.name = field.to_string();
+            continue;
+        }
+        let value = match parse_decimal(field) {
+            Some(value) if value.abs() <= Decimal::from(LIMIT) => value,
+            Some(_) => {
+                errors.push((
+                    Rule::OutOfRange,
+                    format!("{} {} is out of range", column.header(), field),
+                ));
+                continue;
+            }
+            None => {
+                errors.push((
+                    Rule::InvalidNumber,
+                    format!("{} : invalid number {:?}", column.header(), field),
+                ));
+                continue;
+            }
+        };
+        match column {
+            Column::Price => row.price = value,
+            Column::Profit => row.profit = value,
+            Column::ProfitMin => row.profit_min = Some(value),
+            Column::ProfitMax => row.profit_max = Some(value),
+            Column::Risk => row.risk = value,
+            Column::Name => {}
+        }
+    }
+    for column in REQUIRED {
+        if !found.contains(&column) {
+            errors.push((Rule::MissingField, format!("missing {}", column.header())));
+        }
+    }
+    if errors.is_empty() {
+        Ok(row)
+    } else {
+        Err(errors)
+    }
+}
+
+// The rows read, or every error met, each after the place of its record
+pub fn rows_or_errors(rows: Vec<RowBrut>, invalid: &[Invalid]) -> Result<Vec<RowBrut>> {
+    let errors: Vec<String> = invalid
+        .iter()
+        .flat_map(|record| {
+            record
+                .errors
+                .iter()
+                .map(move |(_, message)| format!("{} : {}", record.place, message))
+        })
+        .collect();
+    if !errors.is_empty() {
+        let plural = if errors.len() > 1 { "s" } else { "" };
+        bail!("{} error{}\n{}", errors.len(), plural, errors.join("\n"));
+    }
+    Ok(rows)
+}
+
+// A number written with a decimal point or a decimal comma, the other one
+// and spaces being taken as thousands separators when both are present:
+// 12.5, 12,5, 1 234,5, 1,234.5 and 1.234,5 all read as expected. A lone
+// comma is always a decimal comma.
+fn parse_decimal(field: &str) -> Option<Decimal> {
+    let mut number: String = field
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '\'')
+        .collect();
+    match (number.rfind('.'), number.rfind(',')) {
+        (Some(dot), Some(comma)) if comma > dot => {
+            number = number.replace('.', "").replace(',', ".");
+        }
+        (Some(_), Some(_)) => number = number.replace(',', ""),
+        (None, Some(_)) => number = number.replace(',', "."),
+        _ => {}
+    }
+    Decimal::from_str(&number)
+        .or_else(|_| Decimal::from_scientific(&number))
+        .ok()
+}
@@ -1,19 +1,23 @@
-use std::cmp::{max, min};
-use std::fs::File;
-use std::io::BufReader;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
 use std::str::FromStr;
 use std::time::{Duration, Instant};
 
-use anyhow::Result;
-use plotters::prelude::*;
+use anyhow::{bail, Result};
 // use plotters::prelude::{
 //     AreaSeries, BitMapBackend, ChartBuilder, Color, IntoDrawingArea, LabelAreaPosition,
 //     PathElement, SeriesLabelPosition, BLACK, BLUE, RED, WHITE,
 // };
 // use cached::proc_macro::cached;
-use clap::Parser;
+use cancel::Cancellation;
+use clap::ArgMatches;
+use cli::{BenchArgs, Cli, Command, SolveArgs, SourceArgs};
+use ingest::RowBrut;
 use rust_decimal::Decimal;
-use serde::Deserialize;
+use search::Search;
+use serde::{Deserialize, Serialize};
 
 macro_rules! zero {
     () => {
@@ -21,41 +25,94 @@ macro_rules! zero {
     };
 }
 
-#[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
-struct Args {
-    #[clap(short, long, default_value_t = 500)]
-    balance: usize,
-    #[clap(short, long, default_value_t = 0)]
-    dataset: usize,
-    #[clap(short, long, default_value_t = 0)]
-    algorithme: usize,
-    #[clap(short, long, takes_value = false)]
-    curves: bool,
-}
+mod benchmark;
+mod cancel;
+mod chart;
+mod cli;
+mod compare;
+mod config;
+mod curves;
+mod diff;
+mod explain;
+mod explore;
+mod fitting;
+mod generate;
+mod ingest;
+mod portfolio_chart;
+mod rebalance;
+mod report;
+mod risk;
+mod search;
+mod sensitivity;
+mod serve;
+mod simulation;
+mod sweep;
+#[cfg(test)]
+mod tests;
+mod validate;
 
 #[derive(Debug, Deserialize, Clone)]
-struct RowBrut {
+struct Row {
     name: String,
     price: Decimal,
     profit: Decimal,
+    benefits: Decimal,
+    // range of the profit estimate, used by the simulation
+    profit_min: Option<Decimal>,
+    profit_max: Option<Decimal>,
+    // volatility or risk score, used by the risk-adjusted objectives
+    risk: Decimal,
+    // fields of the dataset besides the ones above
+    metadata: BTreeMap<String, String>,
+    // line of the row in its dataset
+    line: usize,
 }
 
-#[derive(Debug, Deserialize, Clone)]
-struct Row {
+// A share as known beyond a run, the same name being possibly given to
+// several rows of a dataset
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct ShareId {
     name: String,
-    price: Decimal,
-    profit: Decimal,
-    benefits: Decimal,
+    line: usize,
+}
+
+impl fmt::Display for ShareId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (line {})", self.name, self.line)
+    }
 }
 
-impl Into<Row> for RowBrut {
-    fn into(self) -> Row {
+impl Row {
+    fn id(&self) -> ShareId {
+        ShareId {
+            name: self.name.clone(),
+            line: self.line,
+        }
+    }
+
+    // key=value pairs of the metadata
+    fn metadata_text(&self) -> String {
+        let pairs: Vec<String> = self
+            .metadata
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        pairs.join(", ")
+    }
+}
+
+impl From<RowBrut> for Row {
+    fn from(val: RowBrut) -> Self {
         Row {
-            name: self.name,
-            price: self.price,
-            profit: self.profit,
-            benefits: self.price * self.profit / Decimal::from_str("100").unwrap(),
+            name: val.name,
+            price: val.price,
+            profit: val.profit,
+            benefits: val.price * val.profit / Decimal::from_str("100").unwrap(),
+            profit_min: val.profit_min,
+            profit_max: val.profit_max,
+            risk: val.risk,
+            metadata: val.metadata,
+            line: val.line,
         }
     }
 }
@@ -63,8 +120,44 @@ impl Into<Row> for RowBrut {
 #[derive(Debug, Clone)]
 struct Best {
     earnings: Decimal,
+    // indices in the data given to the solver, meaningful within the run only
     actions: Vec<usize>,
     balance: Decimal,
+    // stopped before the end of the search, so not proven optimal
+    interrupted: bool,
+    // the shares of the actions, filled once solved
+    shares: Vec<ShareId>,
+}
+
+// The canonical tie-break of two selections of equal earnings (or score) :
+// the lower cost, then the fewer shares, then the shares (by name, then by
+// line for the rows of the same name) in lexicographic order. Greater when
+// the first selection is preferred.
+fn tie_break(balance: Decimal, actions: &[usize], other: &Best, data: &[Row]) -> Ordering {
+    let ids = |actions: &[usize]| -> Vec<ShareId> {
+        let mut ids: Vec<ShareId> = actions.iter().map(|i| data[*i].id()).collect();
+        ids.sort_unstable();
+        ids
+    };
+    balance
+        .cmp(&other.balance)
+        .then_with(|| other.actions.len().cmp(&actions.len()))
+        .then_with(|| ids(&other.actions).cmp(&ids(actions)))
+}
+
+// Whether a selection is preferred to the best one, by its earnings then by
+// the tie-break, so that every solver picks the same one among equal optima
+fn preferred(
+    earnings: Decimal,
+    balance: Decimal,
+    actions: &[usize],
+    best: &Best,
+    data: &[Row],
+) -> bool {
+    earnings
+        .cmp(&best.earnings)
+        .then_with(|| tie_break(balance, actions, best, data))
+        .is_gt()
 }
 
 #[allow(unused)]
@@ -74,47 +167,88 @@ struct RecursiveCached {
     result: Best,
 }
 
-fn get_csv_dataset(file_number: usize) -> Result<Vec<Row>> {
-    let file = File::open(format!("dataset/dataset{}_Python+P7.csv", file_number))?;
-    let reader = BufReader::new(file);
-    let mut csv_reader = csv::Reader::from_reader(reader);
-    let mut data: Vec<Row> = Vec::new();
-    for result in csv_reader.deserialize() {
-        let row: RowBrut = result?;
-        data.push(row.into());
+fn get_dataset(source: &SourceArgs) -> Result<Vec<Row>> {
+    let rows = ingest::read_dataset(&source.path(), source.format()?)?;
+    Ok(rows.into_iter().map(Row::from).collect())
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+struct Cleaning {
+    rows: usize,
+    // rows removed by each rule, a row may break both
+    non_positive_price: usize,
+    non_positive_profit: usize,
+    kept: usize,
+}
+
+fn clean_data(data: Vec<Row>) -> (Vec<Row>, Cleaning) {
+    // clean data : removes negative prices and profit
+    let mut cleaning = Cleaning {
+        rows: data.len(),
+        ..Default::default()
+    };
+    for row in &data {
+        if row.price <= zero!() {
+            cleaning.non_positive_price += 1;
+        }
+        if row.profit <= zero!() {
+            cleaning.non_positive_profit += 1;
+        }
     }
-    Ok(data)
+    let data: Vec<Row> = data.into_iter().filter(kept).collect();
+    cleaning.kept = data.len();
+    (data, cleaning)
+}
+
+// Whether the cleaning keeps a row
+fn kept(row: &Row) -> bool {
+    row.price > zero!() && row.profit > zero!()
 }
 
-fn optimized_one_loop(data: Vec<Row>, balance: Decimal) -> Result<Best> {
-    fn recursive(balance: Decimal, data: &Vec<Row>) -> Best {
+fn optimized_one_loop(data: Vec<Row>, balance: Decimal, search: &Search) -> Result<Best> {
+    fn recursive(balance: Decimal, data: &[Row], search: &Search) -> Best {
         // Considering that the data are sorted by profit (from the best pourcentage
         // to the lowest), then if the actions is not out of budget, then we buy it !
         let mut best = Best {
             earnings: zero!(),
             actions: Vec::new(),
-            balance: balance.clone(),
+            balance,
+            interrupted: false,
+            shares: Vec::new(),
         };
         for (i, row) in data.iter().enumerate() {
+            if search.should_stop() {
+                break;
+            }
             if best.balance >= row.price && !best.actions.contains(&i) {
                 best.earnings += row.benefits;
                 best.balance -= row.price;
                 best.actions.push(i);
+                search.offer(&best, best.earnings);
             }
+            search.set_covered((i + 1) as f64 / data.len() as f64);
         }
         best
     }
-    Ok(recursive(balance, &data))
+    Ok(recursive(balance, &data, search))
 }
 
-fn optimized_recursive_stack(data: Vec<Row>, balance: Decimal) -> Result<Best> {
-    fn recursive(stack: usize, balance: Decimal, earnings: Decimal, data: &Vec<Row>) -> Best {
+fn optimized_recursive_stack(data: Vec<Row>, balance: Decimal, search: &Search) -> Result<Best> {
+    fn recursive(
+        stack: usize,
+        balance: Decimal,
+        earnings: Decimal,
+        data: &[Row],
+        search: &Search,
+    ) -> Best {
         // Considering that the data are sorted by profit (from the best pourcentage
         // to the lowest), then if the actions is not out of budget, then we buy it !
         let mut current_best = Best {
-            earnings: zero!(),
+            earnings,
             actions: Vec::new(),
-            balance: zero!(),
+            balance,
+            interrupted: false,
+            shares: Vec::new(),
         };
         let mut earnings_increased = zero!();
         // println!(
@@ -123,6 +257,12 @@ fn optimized_recursive_stack(data: Vec<Row>, balance: Decimal) -> Result<Best> {
         // );
         // println!("\nRecursive(#{}, {}€, {}€)", stack, balance, earnings);
         for i in stack..data.len() {
+            if search.should_stop() {
+                break;
+            }
+            if stack == 0 {
+                search.set_covered(i as f64 / data.len() as f64);
+            }
             let row = data[i].clone();
             if balance < row.price {
                 // println!(
@@ -138,38 +278,53 @@ fn optimized_recursive_stack(data: Vec<Row>, balance: Decimal) -> Result<Best> {
                 let mut actions: Vec<usize> = Vec::new();
                 let new_balance = balance - row.price;
                 let new_earnings = earnings + row.benefits;
-                // if new_earnings > current_best.earnings {
-                current_best.earnings = new_earnings.clone();
-                current_best.actions.push(i);
                 actions.push(i);
-                current_best.balance = new_balance.clone();
-                // println!("Inproved loop after ! {:?}", current_best);
-                // }
+                // the shares of the levels above are in both selections, so
+                // the tie-break can compare the ones of this level only
+                if preferred(new_earnings, new_balance, &actions, &current_best, data) {
+                    // replaces the share tried before, whose subtree did not improve
+                    current_best.earnings = new_earnings;
+                    current_best.actions = actions.clone();
+                    current_best.balance = new_balance;
+                    // println!("Inproved loop after ! {:?}", current_best);
+                }
                 // println!(
                 //     "Start recursive #{}:{} => {:?} ; actions {:?}",
                 //     stack, i, current_best, actions
                 // );
-                let result_best = recursive(i + 1, new_balance, new_earnings, &data);
+                let mut result_best = recursive(i + 1, new_balance, new_earnings, data, search);
                 let increased_benefits = result_best.earnings - current_best.earnings;
-                if increased_benefits > zero!() {
+                actions.append(&mut result_best.actions);
+                if preferred(
+                    result_best.earnings,
+                    result_best.balance,
+                    &actions,
+                    &current_best,
+                    data,
+                ) {
                     // println!("Inproved Réc ! {:?} => {:?}", current_best, result_best);
                     earnings_increased = increased_benefits;
-                    actions.append(&mut result_best.actions.clone());
                     current_best = result_best;
                     current_best.actions = actions;
                     // println!("Inproved Réc after ! {:?}", current_best);
                 }
+                // only the first level holds whole selections
+                if stack == 0 {
+                    search.offer(&current_best, current_best.earnings);
+                }
                 // println!("End loop incr {}", earnings_increased);
             }
         }
         // println!("Return #{} : {:?}\n", stack, current_best);
         current_best
     }
-    Ok(recursive(0, balance, zero!(), &data))
+    Ok(recursive(0, balance, zero!(), &data, search))
 }
 
-fn optimized_recursive(data: Vec<Row>, balance: Decimal) -> Result<Best> {
+#[allow(clippy::only_used_in_recursion)]
+fn optimized_recursive(data: Vec<Row>, balance: Decimal, search: &Search) -> Result<Best> {
     let mut cached_recursives: Vec<RecursiveCached> = Vec::new();
+    #[allow(dead_code)]
     #[derive(Debug, Clone)]
     struct NotCachedError;
 
@@ -196,9 +351,10 @@ fn optimized_recursive(data: Vec<Row>, balance: Decimal) -> Result<Best> {
         stack: usize,
         balance: Decimal,
         earnings: Decimal,
-        actions: &Vec<usize>,
-        data: &Vec<Row>,
+        actions: &[usize],
+        data: &[Row],
         cached_recursives: &mut Vec<RecursiveCached>,
+        search: &Search,
     ) -> Best {
         // Considering that the data are sorted by profit (from the best pourcentage
         // to the lowest), then if the actions is not out of budget, then we buy it !
@@ -211,12 +367,20 @@ fn optimized_recursive(data: Vec<Row>, balance: Decimal) -> Result<Best> {
         // }
         let mut current_best = Best {
             earnings,
-            actions: actions.clone(),
+            actions: actions.to_vec(),
             balance,
+            interrupted: false,
+            shares: Vec::new(),
         };
         let mut earnings_increased = zero!();
         // println!("\nRecursive #{}", stack);
         for (i, row) in data.iter().enumerate() {
+            if search.should_stop() {
+                break;
+            }
+            if stack == 1 {
+                search.set_covered(i as f64 / data.len() as f64);
+            }
             if actions.contains(&i) {
                 // println!("Not enough capital. #{} => {}", stack, i + 1);
                 continue;
@@ -229,14 +393,15 @@ fn optimized_recursive(data: Vec<Row>, balance: Decimal) -> Result<Best> {
             } else {
                 let new_balance = balance - row.price;
                 let new_earnings = earnings + row.benefits;
-                let mut new_actions: Vec<usize> = actions.clone();
-                new_actions.push(i.clone());
-                if new_earnings > current_best.earnings {
+                let mut new_actions: Vec<usize> = actions.to_vec();
+                new_actions.push(i);
+                if preferred(new_earnings, new_balance, &new_actions, &current_best, data) {
                     // earnings_increased += current_best.earnings - new_earnings;
                     // earnings_increased += new_earnings - current_best.earnings;
-                    current_best.earnings = new_earnings.clone();
+                    current_best.earnings = new_earnings;
                     current_best.actions = new_actions.clone();
-                    current_best.balance = new_balance.clone();
+                    current_best.balance = new_balance;
+                    search.offer(&current_best, current_best.earnings);
                 }
                 // println!(
                 //     "Loop #{} : {}, {:?} ; actions_recur {:?}",
@@ -250,12 +415,19 @@ fn optimized_recursive(data: Vec<Row>, balance: Decimal) -> Result<Best> {
                     new_balance,
                     new_earnings,
                     &new_actions,
-                    &data,
+                    data,
                     cached_recursives,
+                    search,
                 );
                 // let increased_benefits = result_best.earnings - current_best.earnings;
                 let increased_benefits = result_best.earnings - current_best.earnings;
-                if increased_benefits > zero!() {
+                if preferred(
+                    result_best.earnings,
+                    result_best.balance,
+                    &result_best.actions,
+                    &current_best,
+                    data,
+                ) {
                     earnings_increased = increased_benefits;
                     current_best = result_best;
                     // println!(
@@ -279,14 +451,17 @@ fn optimized_recursive(data: Vec<Row>, balance: Decimal) -> Result<Best> {
         &Vec::new(),
         &data,
         &mut cached_recursives,
+        search,
     ))
 }
 
-fn brut_force_recursive_binary(data: Vec<Row>, balance: Decimal) -> Result<Best> {
+fn brut_force_recursive_binary(data: Vec<Row>, balance: Decimal, search: &Search) -> Result<Best> {
     let mut best = Best {
         earnings: zero!(),
         actions: Vec::new(),
-        balance: zero!(),
+        balance,
+        interrupted: false,
+        shares: Vec::new(),
     };
     let earnings: &mut Decimal = &mut zero!();
     let actions: &mut Vec<usize> = &mut Vec::new();
@@ -294,32 +469,34 @@ fn brut_force_recursive_binary(data: Vec<Row>, balance: Decimal) -> Result<Best>
 
     fn buy_action_and_check_best(
         index: usize,
-        data: &Vec<Row>,
+        data: &[Row],
         balance: &mut Decimal,
         earnings: &mut Decimal,
         actions: &mut Vec<usize>,
         best: &mut Best,
+        search: &Search,
     ) {
         let row: Row = data[index].clone();
-        *balance = *balance - row.price;
-        *earnings = *earnings + row.benefits;
+        *balance -= row.price;
+        *earnings += row.benefits;
         actions.push(index);
-        if *earnings > best.earnings && *balance >= zero!() {
+        if *balance >= zero!() && preferred(*earnings, *balance, actions, best, data) {
             best.earnings = *earnings;
             best.actions = actions.clone().to_owned();
             best.balance = *balance;
+            search.offer(best, best.earnings);
         }
     }
     fn remove_action(
         row_index: usize,
-        data: &Vec<Row>,
+        data: &[Row],
         balance: &mut Decimal,
         earnings: &mut Decimal,
         actions: &mut Vec<usize>,
     ) {
         let row: Row = data[row_index].clone();
-        *balance = *balance + row.price;
-        *earnings = *earnings - row.benefits;
+        *balance += row.price;
+        *earnings -= row.benefits;
         let removed_index = actions.pop().expect("Empty vector actions !");
         if removed_index != row_index {
             panic!("Wrong index removed !")
@@ -328,262 +505,532 @@ fn brut_force_recursive_binary(data: Vec<Row>, balance: Decimal) -> Result<Best>
 
     fn recursive(
         index: usize,
-        data: &Vec<Row>,
+        data: &[Row],
         balance: &mut Decimal,
         earnings: &mut Decimal,
         actions: &mut Vec<usize>,
         best: &mut Best,
+        search: &Search,
     ) {
-        if index < data.len() {
+        if index == data.len() {
+            search.cover_subtree(index);
+        }
+        if index < data.len() && !search.should_stop() {
             let row: Row = data[index].clone();
             // println!("index {} {:?}", index, actions);
             // Whithout the action corresponding to the index
-            recursive(index + 1, data, balance, earnings, actions, best);
+            recursive(index + 1, data, balance, earnings, actions, best, search);
 
-            if *balance > row.price {
+            if *balance >= row.price {
                 // Whith the action corresponding to the index
-                buy_action_and_check_best(index, &data, balance, earnings, actions, best);
+                buy_action_and_check_best(index, data, balance, earnings, actions, best, search);
                 // println!("index {} {:?}", index, actions);
-                recursive(index + 1, data, balance, earnings, actions, best);
+                recursive(index + 1, data, balance, earnings, actions, best, search);
 
                 // clean before return
-                remove_action(index, &data, balance, earnings, actions);
+                remove_action(index, data, balance, earnings, actions);
+            } else {
+                search.cover_subtree(index + 1);
             }
         }
     }
 
-    recursive(0, &data, balance, earnings, actions, &mut best);
+    recursive(0, &data, balance, earnings, actions, &mut best, search);
     Ok(best)
 }
 
-fn brut_force_recursive_redondant(data: Vec<Row>, balance: Decimal) -> Result<Best> {
+fn brut_force_recursive_redondant(
+    data: Vec<Row>,
+    balance: Decimal,
+    search: &Search,
+) -> Result<Best> {
     let best = Best {
         earnings: zero!(),
         actions: Vec::new(),
-        balance: balance.clone(),
+        balance,
+        interrupted: false,
+        shares: Vec::new(),
     };
 
     fn recursive(
         best: Best,
         index: usize,
         // best: &mut Arc<Best>,
-        data: &Vec<Row>,
+        data: &[Row],
+        search: &Search,
     ) -> Best {
         if index >= data.len() {
+            search.cover_subtree(index);
+            return best;
+        }
+        if search.should_stop() {
             return best;
         }
         let row = data[index].clone();
-        let skipped = recursive(best.clone(), index + 1, &data);
+        let skipped = recursive(best.clone(), index + 1, data, search);
         if best.balance >= row.price {
             let mut new_actions = best.actions.clone();
-            new_actions.push(index.clone());
+            new_actions.push(index);
             let new_best = Best {
                 balance: best.balance - row.price,
                 actions: new_actions.clone(),
                 earnings: best.earnings + row.benefits,
+                interrupted: false,
+                shares: Vec::new(),
             };
-            let added = recursive(new_best, index + 1, &data);
+            search.offer(&new_best, new_best.earnings);
+            let added = recursive(new_best, index + 1, data, search);
 
-            if added.earnings > skipped.earnings {
-                return added;
+            if preferred(
+                added.earnings,
+                added.balance,
+                &added.actions,
+                &skipped,
+                data,
+            ) {
+                added
             } else {
-                return skipped;
+                skipped
             }
         } else {
-            return skipped;
+            search.cover_subtree(index + 1);
+            skipped
         }
     }
-    Ok(recursive(best, 0, &data))
+    Ok(recursive(best, 0, &data, search))
 }
 
-fn check_data(best: &Best, data: Vec<Row>, balance: Decimal) {
+#[derive(Debug, Clone)]
+struct Verification {
+    // recomputed from the actions of the result
+    earnings: Decimal,
+    balance: Decimal,
+}
+
+impl Verification {
+    fn earnings_match(&self, best: &Best) -> bool {
+        self.earnings == best.earnings
+    }
+
+    fn balance_match(&self, best: &Best) -> bool {
+        self.balance == best.balance
+    }
+
+    fn within_budget(&self) -> bool {
+        self.balance >= zero!()
+    }
+}
+
+fn verify(best: &Best, data: &[Row], balance: Decimal) -> Verification {
     let mut total_earnings = zero!();
     let mut balance = balance;
-    println!("Best in test {:?}", best);
     for index_row in best.actions.clone() {
         let row = data[index_row].clone();
         total_earnings += row.benefits;
         balance -= row.price;
     }
-    assert_eq!(best.balance, balance);
-    assert!(balance >= zero!());
-    assert_eq!(best.earnings, total_earnings);
-    println!("Checked benefits : {}", total_earnings);
-    println!("Checked balance : {}", balance);
+    Verification {
+        earnings: total_earnings,
+        balance,
+    }
+}
+
+// The row of a share, found by its identity rather than by its index
+fn find_share<'a>(data: &'a [Row], share: &ShareId) -> Result<&'a Row> {
+    match data
+        .iter()
+        .find(|row| row.line == share.line && row.name == share.name)
+    {
+        Some(row) => Ok(row),
+        None => bail!("share {} is not in the dataset", share),
+    }
+}
+
+// What verify recomputes, from the shares of the result instead of its actions
+fn verify_shares(best: &Best, data: &[Row], balance: Decimal) -> Result<Verification> {
+    let mut total_earnings = zero!();
+    let mut balance = balance;
+    for share in &best.shares {
+        let row = find_share(data, share)?;
+        total_earnings += row.benefits;
+        balance -= row.price;
+    }
+    Ok(Verification {
+        earnings: total_earnings,
+        balance,
+    })
 }
 
-fn show_result(data: Vec<Row>, best: &Best, duration: Duration) {
+fn check_data(best: &Best, data: Vec<Row>, balance: Decimal) {
+    let shares: Vec<String> = best.shares.iter().map(|share| share.to_string()).collect();
+    println!("Checked shares : {}", shares.join(", "));
+    let verification = match verify_shares(best, &data, balance) {
+        Ok(verification) => verification,
+        Err(error) => panic!("{}", error),
+    };
+    assert_eq!(best.balance, verification.balance);
+    assert!(verification.within_budget());
+    assert_eq!(best.earnings, verification.earnings);
+    println!("Checked benefits : {}", verification.earnings);
+    println!("Checked balance : {}", verification.balance);
+}
+
+fn show_result(data: &[Row], best: &Best, duration: Duration) -> Result<()> {
     println!("Actions to buy :");
     println!(
-        "Brute force, result : {:?} ; duration : {:?}",
-        best, duration
+        "Result : earnings {} ; balance {} ; {} shares ; duration : {:?}",
+        best.earnings,
+        best.balance,
+        best.shares.len(),
+        duration
     );
-    for index_row in &best.actions {
-        println!("{}", data[*index_row].name);
+    for share in &best.shares {
+        let row = find_share(data, share)?;
+        if row.metadata.is_empty() {
+            println!("{}", share);
+        } else {
+            println!("{} ({})", share, row.metadata_text());
+        }
+    }
+    if best.interrupted {
+        println!("Interrupted : best result found so far, not proven optimal");
     }
+    Ok(())
 }
 
-fn curve_duration(algorithme: usize, data: Vec<Row>, balance: Decimal) -> Result<()> {
-    let mut durations: Vec<i32> = Vec::new();
-    let mut complexity: Vec<i32> = Vec::new();
-    let mut nb_actions: Vec<usize> = Vec::new();
-    let data_len = data.len();
-    println!("datalen {}", data_len);
-    let step;
-    if data_len > 50 {
-        step = 10;
-    } else {
-        step = 1;
-    }
-    for size in (2..data_len).step_by(step) {
-        let safe_size = max(2, min(size, data_len));
-        let n = safe_size as f64;
-        let mut reduced_data = data[0..safe_size].to_vec();
-
-        nb_actions.push(safe_size);
-        let start = Instant::now();
-        if algorithme != 1 {
-            reduced_data.sort_by(|a, b| a.profit.partial_cmp(&b.profit).unwrap());
-            reduced_data.reverse();
-        }
-        run_algorithme(algorithme, reduced_data, balance)?;
-        let end = Instant::now();
-        let algo_duration = end.duration_since(start);
-        println!("Plot {} : {:?}", safe_size, algo_duration);
-        // durations.push(algo_duration.as_micros() as i32);
-        durations.push(algo_duration.as_millis() as i32);
-        // let p_complexity = 50.0 * n.log(10f64);
-        // let p_complexity = 0.3 * n;
-        let p_complexity = 2usize.pow(n as u32) as f64 * 0.0002;
-        // let p_complexity = n * n.log(10f64);
-        complexity.push((p_complexity) as i32);
-    }
-
-    let root_area =
-        BitMapBackend::new("explanations/curve_brut_force.png", (600, 400)).into_drawing_area();
-    root_area.fill(&WHITE).unwrap();
-
-    let mut ctx = ChartBuilder::on(&root_area)
-        .set_label_area_size(LabelAreaPosition::Left, 40)
-        .set_label_area_size(LabelAreaPosition::Bottom, 40)
-        .caption("Temps d'exécution / nombre d'actions", ("sans-serif", 30))
-        .build_cartesian_2d(
-            2..data_len + 1,
-            0..((*durations.iter().max().unwrap() as f64 * 1.1) as i32),
-            // 0..*durations.iter().max().unwrap(),
-            // 0..durations[durations.len() - 1] * 2,
-            // 0..(data_len + 100),
-            // 0..((durations[durations.len() - 1] as f64 * 1.1) as i32),
-        )?;
+const ALGORITHMES: [&str; 7] = [
+    "brut_force_recursive_binary",
+    "brut_force_recursive_redondant",
+    "optimized_recursive",
+    "optimized_one_loop",
+    "optimized_recursive_stack",
+    "risk_brut_force",
+    "risk_greedy",
+];
 
-    ctx.configure_mesh()
-        .x_desc("Nombre d'actions")
-        .y_desc("Durée (ms)")
-        .draw()
-        .unwrap();
-
-    ctx.draw_series(
-        AreaSeries::new(
-            (2..).step_by(step).zip(durations.iter().map(|x| *x)),
-            0,
-            &RED.mix(0.2),
-        )
-        .border_style(&RED),
-    )?
-    .label("brut_force")
-    .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &RED));
-
-    ctx.draw_series(
-        AreaSeries::new(
-            (2..).step_by(step).zip(complexity.iter().map(|x| *x)),
-            0,
-            &BLUE.mix(0.2),
-        )
-        .border_style(&BLUE),
-    )?
-    .label("O(k*2^n) with k = 1/5000")
-    .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &BLUE));
-
-    ctx.draw_series(PointSeries::of_element(
-        (data_len - 4..data_len)
-            .step_by(step)
-            // durations starts x=2
-            .map(|x| (x, durations[x - 2])),
-        5,
-        ShapeStyle::from(&RED).filled(),
-        &|coord, size, style| {
-            EmptyElement::at(coord)
-                + Circle::new((0, 0), size, style)
-                + Text::new(
-                    format!("{}: {:.2}s", coord.0, coord.1 as f64 / 1000.0),
-                    (-69, -4),
-                    ("sans-serif", 15),
-                )
-        },
-    ))?;
-
-    ctx.configure_series_labels()
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
-        .position(SeriesLabelPosition::UpperLeft)
-        .draw()?;
+// Solvers exploring every selection, so returning an optimum when they end
+const EXACT_ALGORITHMES: [usize; 3] = [0, 1, 5];
 
-    Ok(())
+fn algorithme_name(algorithme: usize) -> &'static str {
+    ALGORITHMES.get(algorithme).copied().unwrap_or("unknown")
 }
 
-fn run_algorithme(algorithme: usize, data: Vec<Row>, balance: Decimal) -> Result<Best> {
+fn run_algorithme(
+    algorithme: usize,
+    data: Vec<Row>,
+    balance: Decimal,
+    objective: risk::Objective,
+    search: &Search,
+) -> Result<Best> {
+    if algorithme < 5 && objective != risk::Objective::Benefits {
+        bail!(
+            "algorithme number {} only maximises benefits, use 5 or 6 to {}",
+            algorithme,
+            objective
+        );
+    }
     // Run the algorithme choosen in arguments
-    Ok(match algorithme {
-        0 => brut_force_recursive_binary(data.clone(), balance)?,
-        1 => brut_force_recursive_redondant(data.clone(), balance)?,
-        2 => optimized_recursive(data.clone(), balance)?,
-        3 => optimized_one_loop(data.clone(), balance)?,
-        4 => optimized_recursive_stack(data.clone(), balance)?,
-        val => panic!("algorithme number {} does not exist.", val),
-    })
+    let mut best = match algorithme {
+        0 => brut_force_recursive_binary(data.clone(), balance, search)?,
+        1 => brut_force_recursive_redondant(data.clone(), balance, search)?,
+        2 => optimized_recursive(data.clone(), balance, search)?,
+        3 => optimized_one_loop(data.clone(), balance, search)?,
+        4 => optimized_recursive_stack(data.clone(), balance, search)?,
+        5 => risk::risk_brut_force(data.clone(), balance, objective, search)?,
+        6 => risk::risk_greedy(data.clone(), balance, objective, search)?,
+        val => bail!("no solver {}, expected 0 to {}", val, ALGORITHMES.len() - 1),
+    };
+    // the shares in the order of the data, whatever the order they were bought in
+    best.actions.sort_unstable();
+    best.shares = best.actions.iter().map(|i| data[*i].id()).collect();
+    best.interrupted = search.stopped();
+    search.finish(&best);
+    Ok(best)
 }
 
-fn main() -> Result<()> {
-    // Parse arguments
-    let args: Args = Args::parse();
-    let balance: Decimal = args.balance.into();
-    let dataset_number = args.dataset;
-    let algorithme = args.algorithme;
-    let curves = args.curves;
+// The data as the solvers expect it, by decreasing profit, the ties by
+// increasing price then name. The sort is stable, so that identical rows keep
+// the order of the dataset.
+fn sort_data(data: &mut [Row]) {
+    data.sort_by(data_order);
+}
 
-    let data: Vec<Row> = get_csv_dataset(dataset_number)?;
-    // clean data : removes negative prices and profit
-    let mut data: Vec<Row> = data
-        .into_iter()
-        .filter(|row| row.price > zero!() && row.profit > zero!())
-        .collect();
+fn data_order(a: &Row, b: &Row) -> Ordering {
+    b.profit
+        .cmp(&a.profit)
+        .then_with(|| a.price.cmp(&b.price))
+        .then_with(|| a.name.cmp(&b.name))
+}
+
+// Rows changed for a re-solve, sorted as sort_data does, with the index each
+// one has in the data so that the actions can be mapped back
+fn sort_indexed(mut rows: Vec<(usize, Row)>) -> (Vec<usize>, Vec<Row>) {
+    rows.sort_by(|a, b| data_order(&a.1, &b.1));
+    rows.into_iter().unzip()
+}
 
-    if curves {
-        curve_duration(algorithme, data.clone(), balance)?;
+fn load_dataset(source: &SourceArgs) -> Result<(Vec<Row>, Cleaning)> {
+    let data: Vec<Row> = get_dataset(source)?;
+    Ok(clean_data(data))
+}
+
+fn bench(args: BenchArgs, cancellation: &Cancellation) -> Result<()> {
+    let balance: Decimal = args.dataset.balance.into();
+    let source = &args.dataset.source;
+    let objective = args.objective.objective();
+    let (data, _) = load_dataset(source)?;
+
+    let sizes = args.sizes;
+    let config = benchmark::BenchmarkConfig::new(
+        args.solvers,
+        data.len(),
+        sizes.sizes_from,
+        sizes.sizes_to,
+        sizes.sizes_step,
+        sizes.warmup,
+        sizes.repetitions,
+    )?
+    .stopped_by(cancellation, args.time_limit);
+    let output = sizes.bench_output.unwrap_or(format!(
+        "dataset/curves/curve_complexity_{}.csv",
+        source.name()
+    ));
+    if args.chart {
+        let names: Vec<&str> = config
+            .solvers
+            .iter()
+            .map(|solver| algorithme_name(*solver))
+            .collect();
+        let chart = args.chart_args;
+        let options = chart::ChartOptions::new(
+            chart.chart_output,
+            &format!("curve_{}", names.join("_")),
+            chart.chart_size.unwrap_or((600, 400)),
+            chart.chart_format,
+            chart.lang,
+            chart.chart_title,
+            args.log_scale,
+        );
+        curves::curve_duration(
+            &config,
+            data,
+            balance,
+            objective,
+            &output,
+            &args.reference,
+            &options,
+        )?;
     } else {
-        // Start benchmark's clock
-        let start = Instant::now();
-        // sort data by profit (in pourcentage of the price)
-        data.sort_by(|a, b| a.profit.partial_cmp(&b.profit).unwrap());
-        data.reverse();
-        // Result benchmark's clock
-        let end = Instant::now();
-        let duration = end.duration_since(start);
-        println!("Sorting duration : {:?}", duration);
-
-        // Start benchmark's clock
-        let start = Instant::now();
-
-        let best = run_algorithme(algorithme, data.clone(), balance)?;
-
-        // Result benchmark's clock
-        let end = Instant::now();
-        let duration = end.duration_since(start);
-        show_result(data.clone(), &best, duration);
+        let measures = benchmark::run_benchmark(&config, &data, balance, objective)?;
+        benchmark::write_csv(&output, &measures)?;
+        fitting::show_fits(&config.solvers, &measures, args.dataset.balance as f64);
+    }
+    Ok(())
+}
+
+fn solve(args: SolveArgs, cancellation: &Cancellation) -> Result<()> {
+    // the rebalancing only shows its trades, and a profile may give the
+    // holdings that the command line cannot check against the other flags
+    if args.holdings.is_some() {
+        let ignored: Vec<&str> = [
+            ("--json-lines", args.json_lines.is_some()),
+            ("--simulate", args.simulate.is_some()),
+            ("--sensitivity", args.sensitivity),
+            ("--explain", args.explain),
+            ("--portfolio-chart", args.portfolio_chart.is_some()),
+            ("--report", args.report.is_some()),
+            ("--json", args.json.is_some()),
+        ]
+        .iter()
+        .filter(|(_, given)| *given)
+        .map(|(flag, _)| *flag)
+        .collect();
+        if !ignored.is_empty() {
+            bail!("{} cannot be used with --holdings", ignored.join(", "));
+        }
+    }
+    let balance: Decimal = args.dataset.balance.into();
+    let source = &args.dataset.source;
+    let algorithme = args.algorithme;
+    let objective = args.objective.objective();
+    // the rebalancing sells the held shares that the cleaning removes
+    let rows = get_dataset(source)?;
+    let (mut data, cleaning) = clean_data(rows.clone());
+
+    // Start benchmark's clock
+    let start = Instant::now();
+    sort_data(&mut data);
+    // Result benchmark's clock
+    let end = Instant::now();
+    let sorting_duration = end.duration_since(start);
+    println!("Sorting duration : {:?}", sorting_duration);
+
+    let mut search = Search::new(cancellation.with_time_limit(args.time_limit));
+    if args.progress {
+        search = search.with_observer(Box::new(search::ProgressObserver::new()?));
+    }
+    if let Some(path) = &args.json_lines {
+        let names = data.iter().map(|row| row.name.clone()).collect();
+        search = search.with_observer(Box::new(search::JsonLinesObserver::new(path, names)?));
+    }
+
+    if let Some(path) = args.holdings {
+        let mut data = rows;
+        sort_data(&mut data);
+        let holdings = rebalance::get_holdings(&path)?;
+        let rebalancing = rebalance::rebalance(
+            algorithme,
+            &data,
+            &holdings,
+            balance,
+            args.sell_fees,
+            objective,
+            &search,
+        )?;
+        rebalance::show_rebalancing(&data, &rebalancing, balance, args.sell_fees);
 
         // Verify the coherence of the result
-        check_data(&best, data.clone(), balance);
+        check_data(
+            &rebalancing.best,
+            rebalancing.universe.clone(),
+            rebalancing.budget,
+        );
+        return Ok(());
+    }
+
+    // Start benchmark's clock
+    let start = Instant::now();
+
+    let best = run_algorithme(algorithme, data.clone(), balance, objective, &search)?;
+
+    // Result benchmark's clock
+    let end = Instant::now();
+    let duration = end.duration_since(start);
+    show_result(&data, &best, duration)?;
+    if args.explain {
+        let explanations = explain::explain(&data, &best, balance);
+        explain::show_explanation(&data, &explanations);
+    }
+
+    if algorithme >= 5 {
+        risk::show_objective(&data, &best, objective);
+    }
+
+    // Verify the coherence of the result
+    check_data(&best, data.clone(), balance);
+
+    if let Some(samples) = args.simulate {
+        let simulation = simulation::simulate(
+            algorithme,
+            &data,
+            &best,
+            balance,
+            samples,
+            args.seed,
+            objective,
+            cancellation,
+            args.time_limit,
+        )?;
+        simulation::show_simulation(&data, &best, &simulation, args.seed);
+    }
+
+    let chart = &args.chart;
+    if let Some(output) = args.portfolio_chart {
+        let options = chart::ChartOptions::new(
+            output,
+            &format!("portfolio_{}", source.name()),
+            chart.chart_size.unwrap_or((1200, 900)),
+            chart.chart_format,
+            chart.lang,
+            chart.chart_title.clone(),
+            false,
+        );
+        portfolio_chart::portfolio_chart(&data, &best, balance, &options)?;
+    }
+
+    if let Some(path) = args.report {
+        let verification = verify(&best, &data, balance);
+        let report = report::Report {
+            dataset: source.path(),
+            data: &data,
+            cleaning: &cleaning,
+            algorithme,
+            objective,
+            balance,
+            best: &best,
+            verification: &verification,
+            sorting_duration,
+            solving_duration: duration,
+            language: chart.lang,
+        };
+        report::write_report(&path, &report)?;
+    }
+
+    if let Some(path) = &args.json {
+        let result = serve::solve_response(
+            algorithme, objective, &data, &best, balance, &cleaning, duration,
+        )?;
+        if let Err(error) = fs::write(path, serde_json::to_string_pretty(&result)? + "\n") {
+            bail!("cannot write {} : {}", path, error);
+        }
+    }
+
+    if args.sensitivity {
+        let report = sensitivity::sensitivity(
+            algorithme,
+            &data,
+            &best,
+            balance,
+            objective,
+            cancellation,
+            args.time_limit,
+        )?;
+        sensitivity::show_sensitivity(&data, &report);
     }
 
     Ok(())
 }
+
+// The command to run, its flags not given taking the values of the profile
+fn command(cli: Cli, matches: &ArgMatches) -> Result<Command> {
+    let profile = match &cli.profile {
+        Some(name) => Some(config::load_profile(&cli.config, name)?),
+        None => None,
+    };
+    // the flags given on the command line take precedence over the profile
+    let command = match (cli.command, matches.subcommand()) {
+        (Some(mut command), Some((_, command_matches))) => {
+            if let Some(profile) = &profile {
+                profile.apply(&mut command, command_matches);
+            }
+            command
+        }
+        (_, _) => {
+            let mut legacy = cli.legacy;
+            if let Some(profile) = &profile {
+                legacy.apply_profile(profile, matches);
+            }
+            legacy.into_command()
+        }
+    };
+    Ok(command)
+}
+
+fn main() -> Result<()> {
+    // Parse arguments
+    let (cli, matches) = match Cli::parse_with_matches(std::env::args_os()) {
+        Ok(parsed) => parsed,
+        Err(error) => error.exit(),
+    };
+    match command(cli, &matches)? {
+        Command::Solve(args) => solve(args, &Cancellation::on_ctrl_c()?),
+        Command::Bench(args) => bench(args, &Cancellation::on_ctrl_c()?),
+        Command::Compare(args) => compare::compare(&args, &Cancellation::on_ctrl_c()?),
+        Command::Validate(args) => validate::validate(&args),
+        Command::Generate(args) => generate::generate(&args),
+        Command::Serve(args) => serve::serve(&args),
+        Command::Explore(args) => explore::explore(&args, &Cancellation::on_ctrl_c()?),
+        Command::Diff(args) => diff::show_diff(&args, &Cancellation::on_ctrl_c()?),
+        Command::Sweep(args) => sweep::sweep(&args, &Cancellation::on_ctrl_c()?),
+    }
+}
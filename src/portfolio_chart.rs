@@ -0,0 +1,271 @@
+use anyhow::Result;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use crate::chart::{ChartFormat, ChartOptions};
+use crate::{Best, Row};
+
+// One page with the bar chart of the chosen shares, the scatter plot of all
+// the shares and the pie chart of the budget.
+struct PortfolioChart<'a> {
+    data: &'a [Row],
+    best: &'a Best,
+    balance: f64,
+}
+
+fn to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap()
+}
+
+fn color(i: usize) -> RGBColor {
+    let (r, g, b) = Palette99::COLORS[i % Palette99::COLORS.len()];
+    RGBColor(r, g, b)
+}
+
+pub fn portfolio_chart(
+    data: &[Row],
+    best: &Best,
+    balance: Decimal,
+    options: &ChartOptions,
+) -> Result<()> {
+    let chart = PortfolioChart {
+        data,
+        best,
+        balance: to_f64(balance),
+    };
+    let size = options.size;
+    match options.format {
+        ChartFormat::Png => chart.draw(
+            BitMapBackend::new(&options.output, size).into_drawing_area(),
+            options,
+        )?,
+        ChartFormat::Svg => chart.draw(
+            SVGBackend::new(&options.output, size).into_drawing_area(),
+            options,
+        )?,
+    }
+    println!("Portfolio chart written to {}", options.output);
+    Ok(())
+}
+
+// The same page rendered as an SVG document in memory, to be embedded
+pub fn portfolio_svg(
+    data: &[Row],
+    best: &Best,
+    balance: Decimal,
+    options: &ChartOptions,
+) -> Result<String> {
+    let chart = PortfolioChart {
+        data,
+        best,
+        balance: to_f64(balance),
+    };
+    let mut svg = String::new();
+    chart.draw(
+        SVGBackend::with_string(&mut svg, options.size).into_drawing_area(),
+        options,
+    )?;
+    Ok(svg)
+}
+
+impl<'a> PortfolioChart<'a> {
+    fn draw<DB: DrawingBackend>(
+        &self,
+        root_area: DrawingArea<DB, Shift>,
+        options: &ChartOptions,
+    ) -> Result<()>
+    where
+        DB::ErrorType: 'static,
+    {
+        let labels = options.language.portfolio_labels();
+        root_area.fill(&WHITE)?;
+        let root_area = root_area.titled(&options.title(labels.title), ("sans-serif", 30))?;
+        let (top, bottom) = root_area.split_vertically(root_area.dim_in_pixel().1 / 2);
+        let (left, right) = bottom.split_horizontally(bottom.dim_in_pixel().0 / 2);
+        self.draw_bars(&top, options)?;
+        self.draw_scatter(&left, options)?;
+        self.draw_pie(&right, options)?;
+        root_area.present()?;
+        Ok(())
+    }
+
+    fn draw_bars<DB: DrawingBackend>(
+        &self,
+        area: &DrawingArea<DB, Shift>,
+        options: &ChartOptions,
+    ) -> Result<()>
+    where
+        DB::ErrorType: 'static,
+    {
+        let labels = options.language.portfolio_labels();
+        let chosen: Vec<&Row> = self.best.actions.iter().map(|i| &self.data[*i]).collect();
+        let max_price = chosen
+            .iter()
+            .map(|row| to_f64(row.price))
+            .fold(1.0, f64::max);
+
+        let mut ctx = ChartBuilder::on(area)
+            .margin(10)
+            .set_label_area_size(LabelAreaPosition::Left, 50)
+            .set_label_area_size(LabelAreaPosition::Bottom, 60)
+            .caption(labels.bars, ("sans-serif", 20))
+            .build_cartesian_2d(0.0..chosen.len().max(1) as f64, 0.0..max_price * 1.1)?;
+
+        let name = |x: &f64| match chosen.get(*x as usize) {
+            Some(row) if x.fract() == 0.0 => row.name.clone(),
+            _ => String::new(),
+        };
+        ctx.configure_mesh()
+            .disable_x_mesh()
+            .x_labels(chosen.len() + 1)
+            .x_label_formatter(&name)
+            .x_label_style(
+                ("sans-serif", 10)
+                    .into_font()
+                    .transform(FontTransform::Rotate90),
+            )
+            .draw()?;
+
+        ctx.draw_series(chosen.iter().enumerate().map(|(i, row)| {
+            let x = i as f64;
+            Rectangle::new(
+                [(x + 0.1, 0.0), (x + 0.5, to_f64(row.price))],
+                color(0).filled(),
+            )
+        }))?
+        .label(labels.cost)
+        .legend(|(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], color(0).filled()));
+
+        ctx.draw_series(chosen.iter().enumerate().map(|(i, row)| {
+            let x = i as f64;
+            Rectangle::new(
+                [(x + 0.5, 0.0), (x + 0.9, to_f64(row.benefits))],
+                color(1).filled(),
+            )
+        }))?
+        .label(labels.benefit)
+        .legend(|(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], color(1).filled()));
+
+        ctx.configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .position(SeriesLabelPosition::UpperRight)
+            .draw()?;
+        Ok(())
+    }
+
+    fn draw_scatter<DB: DrawingBackend>(
+        &self,
+        area: &DrawingArea<DB, Shift>,
+        options: &ChartOptions,
+    ) -> Result<()>
+    where
+        DB::ErrorType: 'static,
+    {
+        let labels = options.language.portfolio_labels();
+        let points: Vec<(f64, f64, bool)> = self
+            .data
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                (
+                    to_f64(row.price),
+                    to_f64(row.profit),
+                    self.best.actions.contains(&i),
+                )
+            })
+            .collect();
+        let max_price = points.iter().map(|p| p.0).fold(1.0, f64::max);
+        let max_profit = points.iter().map(|p| p.1).fold(1.0, f64::max);
+
+        let mut ctx = ChartBuilder::on(area)
+            .margin(10)
+            .set_label_area_size(LabelAreaPosition::Left, 50)
+            .set_label_area_size(LabelAreaPosition::Bottom, 40)
+            .caption(labels.scatter, ("sans-serif", 20))
+            .build_cartesian_2d(0.0..max_price * 1.05, 0.0..max_profit * 1.05)?;
+
+        ctx.configure_mesh()
+            .x_desc(labels.price)
+            .y_desc(labels.profit)
+            .draw()?;
+
+        ctx.draw_series(
+            points
+                .iter()
+                .filter(|p| !p.2)
+                .map(|p| Circle::new((p.0, p.1), 2, BLACK.mix(0.3).filled())),
+        )?
+        .label(labels.rejected)
+        .legend(|(x, y)| Circle::new((x + 5, y), 2, BLACK.mix(0.3).filled()));
+
+        ctx.draw_series(
+            points
+                .iter()
+                .filter(|p| p.2)
+                .map(|p| Circle::new((p.0, p.1), 4, RED.filled())),
+        )?
+        .label(labels.chosen)
+        .legend(|(x, y)| Circle::new((x + 5, y), 4, RED.filled()));
+
+        ctx.configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .position(SeriesLabelPosition::UpperRight)
+            .draw()?;
+        Ok(())
+    }
+
+    fn draw_pie<DB: DrawingBackend>(
+        &self,
+        area: &DrawingArea<DB, Shift>,
+        options: &ChartOptions,
+    ) -> Result<()>
+    where
+        DB::ErrorType: 'static,
+    {
+        let labels = options.language.portfolio_labels();
+        let area = area.titled(labels.pie, ("sans-serif", 20))?;
+        let (width, height) = area.dim_in_pixel();
+        // the pie is placed in the coordinates of the whole page
+        let (x_range, y_range) = area.get_pixel_range();
+        let center = (
+            x_range.start + width as i32 / 2,
+            y_range.start + height as i32 / 2,
+        );
+        let radius = width.min(height) as f64 * 0.35;
+
+        let mut sizes = Vec::new();
+        let mut colors = Vec::new();
+        let mut names = Vec::new();
+        for (slice, i) in self.best.actions.iter().enumerate() {
+            let row = &self.data[*i];
+            let size = to_f64(row.price);
+            sizes.push(size);
+            colors.push(color(slice));
+            // only the slices large enough to be read are named
+            if size >= self.balance * 0.04 {
+                names.push(row.name.clone());
+            } else {
+                names.push(String::new());
+            }
+        }
+        let leftover = to_f64(self.best.balance);
+        if leftover > 0.0 {
+            sizes.push(leftover);
+            colors.push(RGBColor(220, 220, 220));
+            names.push(labels.leftover.to_string());
+        }
+        if sizes.is_empty() {
+            return Ok(());
+        }
+        let mut pie = Pie::new(&center, &radius, &sizes, &colors, &names);
+        pie.start_angle(-90.0);
+        pie.label_style(("sans-serif", 12).into_font().color(&BLACK));
+        pie.label_offset(radius * 0.25);
+        area.draw(&pie)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::risk::Objective;
+use crate::search::Search;
+use crate::{kept, run_algorithme, sort_indexed, Best, Row};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Holding {
+    name: String,
+    // line of the share in the dataset, to tell apart the rows of a same name
+    #[serde(default)]
+    line: Option<usize>,
+}
+
+impl fmt::Display for Holding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} (line {})", self.name, line),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+// The shares of a JSON result, written by solve --json
+#[derive(Deserialize)]
+struct ResultFile {
+    shares: Vec<Holding>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trade {
+    Buy,
+    Sell,
+    Hold,
+}
+
+#[derive(Debug, Clone)]
+pub struct Rebalancing {
+    // (trade, index of the share in the sorted rows)
+    pub trades: Vec<(Trade, usize)>,
+    pub best: Best,
+    // rows re-priced for the solver and the budget they were solved with
+    pub universe: Vec<Row>,
+    pub budget: Decimal,
+}
+
+// The held shares, from a CSV file of a name column (and optionally a line
+// one) or from a JSON result, so that a result can be rebalanced later
+pub fn get_holdings(path: &str) -> Result<Vec<Holding>> {
+    if path.to_lowercase().ends_with(".json") {
+        let content = fs::read_to_string(path)?;
+        return match serde_json::from_str::<ResultFile>(&content) {
+            Ok(result) => Ok(result.shares),
+            Err(error) => bail!("{} is not a JSON result : {}", path, error),
+        };
+    }
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let mut csv_reader = csv::Reader::from_reader(reader);
+    let mut holdings: Vec<Holding> = Vec::new();
+    for result in csv_reader.deserialize() {
+        let holding: Holding = result?;
+        holdings.push(holding);
+    }
+    Ok(holdings)
+}
+
+// a share without price brings nothing
+fn net_proceeds(row: &Row, sell_fees: Decimal) -> Decimal {
+    let net = row.price - row.price * sell_fees / Decimal::from_str("100").unwrap();
+    net.max(zero!())
+}
+
+// The rows are the ones of the dataset before its cleaning, so that a held
+// share the cleaning removes is found, and sold
+pub fn rebalance(
+    algorithme: usize,
+    data: &[Row],
+    holdings: &[Holding],
+    cash: Decimal,
+    sell_fees: Decimal,
+    objective: Objective,
+    search: &Search,
+) -> Result<Rebalancing> {
+    if sell_fees < zero!() || sell_fees >= Decimal::from_str("100").unwrap() {
+        bail!(
+            "sell fees must be a percentage in [0, 100[, got {}",
+            sell_fees
+        );
+    }
+    let mut held = vec![false; data.len()];
+    for holding in holdings {
+        let position = data.iter().position(|row| {
+            row.name == holding.name && holding.line.is_none_or(|line| row.line == line)
+        });
+        match position {
+            Some(i) if held[i] => bail!("share {} is held twice", holding),
+            Some(i) => held[i] = true,
+            None => bail!("held share {} is not in the dataset", holding),
+        }
+    }
+
+    // Selling a held share frees its net proceeds, so keeping it costs exactly
+    // what we would have got by selling it. Once every held share is priced
+    // that way, rebalancing is the same knapsack as buying from scratch with
+    // the cash plus the proceeds of selling everything.
+    let mut budget = cash;
+    let mut universe: Vec<(usize, Row)> = Vec::new();
+    let mut sold = vec![false; data.len()];
+    for (i, row) in data.iter().enumerate() {
+        let mut row = row.clone();
+        if !kept(&row) {
+            // no solver may buy it back
+            if held[i] {
+                budget += net_proceeds(&row, sell_fees);
+                sold[i] = true;
+            }
+            continue;
+        }
+        if held[i] {
+            let cost = net_proceeds(&row, sell_fees);
+            budget += cost;
+            row.profit = row.benefits * Decimal::from_str("100").unwrap() / cost;
+            row.price = cost;
+        }
+        universe.push((i, row));
+    }
+    // the solvers expect the data sorted by profit
+    let (indexes, universe) = sort_indexed(universe);
+
+    let best = run_algorithme(algorithme, universe.clone(), budget, objective, search)?;
+
+    let mut bought = vec![false; data.len()];
+    for action in &best.actions {
+        bought[indexes[*action]] = true;
+    }
+    let mut trades = Vec::new();
+    for i in 0..data.len() {
+        if sold[i] {
+            trades.push((Trade::Sell, i));
+            continue;
+        }
+        match (held[i], bought[i]) {
+            (true, true) => trades.push((Trade::Hold, i)),
+            (true, false) => trades.push((Trade::Sell, i)),
+            (false, true) => trades.push((Trade::Buy, i)),
+            (false, false) => {}
+        }
+    }
+    Ok(Rebalancing {
+        trades,
+        best,
+        universe,
+        budget,
+    })
+}
+
+pub fn show_rebalancing(
+    data: &[Row],
+    rebalancing: &Rebalancing,
+    cash: Decimal,
+    sell_fees: Decimal,
+) {
+    let mut proceeds = zero!();
+    let mut fees = zero!();
+    let mut purchases = zero!();
+    println!("Trades :");
+    for trade in [Trade::Sell, Trade::Buy, Trade::Hold] {
+        for (_, i) in rebalancing.trades.iter().filter(|(t, _)| *t == trade) {
+            let row = &data[*i];
+            match trade {
+                Trade::Sell => {
+                    let net = net_proceeds(row, sell_fees);
+                    proceeds += net;
+                    fees += row.price - net;
+                    println!("SELL {} : +{}€", row.name, net);
+                }
+                Trade::Buy => {
+                    purchases += row.price;
+                    println!("BUY  {} : -{}€", row.name, row.price);
+                }
+                Trade::Hold => println!("HOLD {}", row.name),
+            }
+        }
+    }
+    println!("Cash before : {}€", cash);
+    println!("Sales : +{}€ (fees {}€)", proceeds, fees);
+    println!("Purchases : -{}€", purchases);
+    println!("Cash after : {}€", cash + proceeds - purchases);
+    println!("Expected benefits : {}€", rebalancing.best.earnings);
+    if rebalancing.best.interrupted {
+        println!("Interrupted : best rebalancing found so far, not proven optimal");
+    }
+}
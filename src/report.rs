@@ -0,0 +1,238 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::time::Duration;
+
+use anyhow::Result;
+use rust_decimal::Decimal;
+
+use crate::chart::{ChartOptions, Language};
+use crate::portfolio_chart;
+use crate::risk::{self, Objective};
+use crate::{algorithme_name, Best, Cleaning, Row, Verification};
+
+// Everything known about a solved run, gathered in a single HTML page
+pub struct Report<'a> {
+    pub dataset: String,
+    // the cleaned data, sorted as given to the solver
+    pub data: &'a [Row],
+    pub cleaning: &'a Cleaning,
+    pub algorithme: usize,
+    pub objective: Objective,
+    pub balance: Decimal,
+    pub best: &'a Best,
+    pub verification: &'a Verification,
+    pub sorting_duration: Duration,
+    pub solving_duration: Duration,
+    pub language: Language,
+}
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em auto; max-width: 1200px; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: right; }
+th { background: #eee; }
+td:first-child, th:first-child { text-align: left; }
+.ok { color: #080; }
+.failed { color: #c00; font-weight: bold; }";
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn status(ok: bool) -> &'static str {
+    if ok {
+        "<span class=\"ok\">ok</span>"
+    } else {
+        "<span class=\"failed\">failed</span>"
+    }
+}
+
+// A two columns table of names and values, the values being already HTML
+fn key_values(html: &mut String, rows: &[(&str, String)]) -> Result<()> {
+    writeln!(html, "<table>")?;
+    for (key, value) in rows {
+        writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", escape(key), value)?;
+    }
+    writeln!(html, "</table>")?;
+    Ok(())
+}
+
+fn range(values: impl Iterator<Item = Decimal>) -> String {
+    let values: Vec<Decimal> = values.collect();
+    match (values.iter().min(), values.iter().max()) {
+        (Some(min), Some(max)) => format!("{} – {}", min, max),
+        _ => "-".to_string(),
+    }
+}
+
+fn render(report: &Report) -> Result<String> {
+    let data = report.data;
+    let best = report.best;
+    let verification = report.verification;
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>")?;
+    writeln!(html, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(html, "<title>Report of {}</title>", escape(&report.dataset))?;
+    writeln!(html, "<style>\n{}\n</style>\n</head>\n<body>", STYLE)?;
+    writeln!(html, "<h1>Report of {}</h1>", escape(&report.dataset))?;
+
+    writeln!(html, "<h2>Dataset</h2>")?;
+    let total_cost: Decimal = data.iter().map(|row| row.price).sum();
+    key_values(
+        &mut html,
+        &[
+            ("File", escape(&report.dataset)),
+            ("Shares", data.len().to_string()),
+            ("Total cost", total_cost.to_string()),
+            ("Prices", range(data.iter().map(|row| row.price))),
+            ("Profits (%)", range(data.iter().map(|row| row.profit))),
+        ],
+    )?;
+
+    writeln!(html, "<h2>Cleaning</h2>")?;
+    let cleaning = report.cleaning;
+    key_values(
+        &mut html,
+        &[
+            ("Rows read", cleaning.rows.to_string()),
+            (
+                "Non positive price",
+                cleaning.non_positive_price.to_string(),
+            ),
+            (
+                "Non positive profit",
+                cleaning.non_positive_profit.to_string(),
+            ),
+            ("Removed", (cleaning.rows - cleaning.kept).to_string()),
+            ("Kept", cleaning.kept.to_string()),
+        ],
+    )?;
+
+    writeln!(html, "<h2>Solver</h2>")?;
+    key_values(
+        &mut html,
+        &[
+            ("Algorithme", escape(algorithme_name(report.algorithme))),
+            ("Objective", escape(&report.objective.to_string())),
+            ("Balance", report.balance.to_string()),
+            (
+                "Search",
+                if best.interrupted {
+                    "interrupted, not proven optimal".to_string()
+                } else {
+                    "complete".to_string()
+                },
+            ),
+        ],
+    )?;
+
+    writeln!(html, "<h2>Chosen shares</h2>")?;
+    // the metadata of the dataset, when it has any
+    let metadata = best
+        .actions
+        .iter()
+        .any(|index| !data[*index].metadata.is_empty());
+    writeln!(
+        html,
+        "<table>\n<tr><th>Name</th><th>Price</th><th>Profit (%)</th><th>Benefits</th><th>Risk</th>{}</tr>",
+        if metadata { "<th>Metadata</th>" } else { "" }
+    )?;
+    for index in &best.actions {
+        let row = &data[*index];
+        write!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>",
+            escape(&row.name),
+            row.price,
+            row.profit,
+            row.benefits,
+            row.risk
+        )?;
+        if metadata {
+            write!(html, "<td>{}</td>", escape(&row.metadata_text()))?;
+        }
+        writeln!(html, "</tr>")?;
+    }
+    writeln!(html, "</table>")?;
+
+    writeln!(html, "<h2>Totals</h2>")?;
+    let spent = report.balance - best.balance;
+    key_values(
+        &mut html,
+        &[
+            ("Shares bought", best.actions.len().to_string()),
+            ("Spent", spent.to_string()),
+            ("Earnings", best.earnings.to_string()),
+            ("Leftover", best.balance.to_string()),
+            ("Risk", risk::total_risk(data, best).to_string()),
+        ],
+    )?;
+
+    writeln!(html, "<h2>Verification</h2>")?;
+    key_values(
+        &mut html,
+        &[
+            (
+                "Earnings",
+                format!(
+                    "{} {}",
+                    verification.earnings,
+                    status(verification.earnings_match(best))
+                ),
+            ),
+            (
+                "Balance",
+                format!(
+                    "{} {}",
+                    verification.balance,
+                    status(verification.balance_match(best))
+                ),
+            ),
+            (
+                "Within budget",
+                status(verification.within_budget()).to_string(),
+            ),
+        ],
+    )?;
+
+    writeln!(html, "<h2>Timings</h2>")?;
+    key_values(
+        &mut html,
+        &[
+            ("Sorting", format!("{:?}", report.sorting_duration)),
+            ("Solving", format!("{:?}", report.solving_duration)),
+        ],
+    )?;
+
+    writeln!(html, "<h2>Charts</h2>")?;
+    let options = ChartOptions::new(
+        None,
+        "portfolio",
+        (1200, 900),
+        None,
+        report.language,
+        None,
+        false,
+    );
+    let svg = portfolio_chart::portfolio_svg(data, best, report.balance, &options)?;
+    writeln!(html, "{}", svg)?;
+
+    writeln!(html, "</body>\n</html>")?;
+    Ok(html)
+}
+
+pub fn write_report(path: &str, report: &Report) -> Result<()> {
+    fs::write(path, render(report)?)?;
+    println!("Report written to {}", path);
+    Ok(())
+}
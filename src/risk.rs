@@ -0,0 +1,217 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use rust_decimal::Decimal;
+
+use crate::search::Search;
+use crate::{tie_break, Best, Row};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    // maximise benefits
+    Benefits,
+    // maximise benefits − λ·risk
+    Penalized { lambda: Decimal },
+    // maximise benefits subject to total risk ≤ R
+    RiskBudget { max_risk: Decimal },
+}
+
+impl Objective {
+    pub fn score(&self, earnings: Decimal, risk: Decimal) -> Decimal {
+        match self {
+            Objective::Penalized { lambda } => earnings - *lambda * risk,
+            _ => earnings,
+        }
+    }
+
+    fn allows(&self, risk: Decimal) -> bool {
+        match self {
+            Objective::RiskBudget { max_risk } => risk <= *max_risk,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Objective::Benefits => write!(f, "maximise benefits"),
+            Objective::Penalized { lambda } => {
+                write!(f, "maximise benefits − λ·risk with λ = {}", lambda)
+            }
+            Objective::RiskBudget { max_risk } => {
+                write!(f, "maximise benefits with total risk ≤ R = {}", max_risk)
+            }
+        }
+    }
+}
+
+// λ and R weigh the risk, a negative one would reward it or leave no
+// selection at all
+pub fn check_weight(name: &str, value: Decimal) -> Result<Decimal> {
+    if value < zero!() {
+        bail!("{} must be positive or zero, got {}", name, value);
+    }
+    Ok(value)
+}
+
+pub fn parse_lambda(value: &str) -> Result<Decimal> {
+    match Decimal::from_str(value) {
+        Ok(lambda) => check_weight("lambda", lambda),
+        Err(_) => bail!("invalid number {}", value),
+    }
+}
+
+pub fn parse_max_risk(value: &str) -> Result<Decimal> {
+    match Decimal::from_str(value) {
+        Ok(max_risk) => check_weight("max-risk", max_risk),
+        Err(_) => bail!("invalid number {}", value),
+    }
+}
+
+pub fn total_risk(data: &[Row], best: &Best) -> Decimal {
+    best.actions.iter().map(|i| data[*i].risk).sum()
+}
+
+pub fn risk_brut_force(
+    data: Vec<Row>,
+    balance: Decimal,
+    objective: Objective,
+    search: &Search,
+) -> Result<Best> {
+    // Same exploration as brut_force_recursive_redondant, keeping the risk of
+    // each branch to prune the ones out of the risk budget and to score them.
+    #[derive(Clone)]
+    struct Branch {
+        best: Best,
+        risk: Decimal,
+    }
+
+    fn recursive(
+        branch: Branch,
+        index: usize,
+        data: &[Row],
+        objective: Objective,
+        search: &Search,
+    ) -> Branch {
+        if index >= data.len() {
+            search.cover_subtree(index);
+            return branch;
+        }
+        if search.should_stop() {
+            return branch;
+        }
+        let row = &data[index];
+        let skipped = recursive(branch.clone(), index + 1, data, objective, search);
+        let risk = branch.risk + row.risk;
+        if branch.best.balance < row.price || !objective.allows(risk) {
+            search.cover_subtree(index + 1);
+            return skipped;
+        }
+        let mut actions = branch.best.actions.clone();
+        actions.push(index);
+        let best = Best {
+            earnings: branch.best.earnings + row.benefits,
+            actions,
+            balance: branch.best.balance - row.price,
+            interrupted: false,
+            shares: Vec::new(),
+        };
+        search.offer(&best, objective.score(best.earnings, risk));
+        let added = recursive(Branch { best, risk }, index + 1, data, objective, search);
+        let added_score = objective.score(added.best.earnings, added.risk);
+        let skipped_score = objective.score(skipped.best.earnings, skipped.risk);
+        let ordering = added_score.cmp(&skipped_score).then_with(|| {
+            let added = &added.best;
+            tie_break(added.balance, &added.actions, &skipped.best, data)
+        });
+        if ordering.is_gt() {
+            added
+        } else {
+            skipped
+        }
+    }
+
+    let branch = Branch {
+        best: Best {
+            earnings: zero!(),
+            actions: Vec::new(),
+            balance,
+            interrupted: false,
+            shares: Vec::new(),
+        },
+        risk: zero!(),
+    };
+    Ok(recursive(branch, 0, &data, objective, search).best)
+}
+
+pub fn risk_greedy(
+    data: Vec<Row>,
+    balance: Decimal,
+    objective: Objective,
+    search: &Search,
+) -> Result<Best> {
+    // Buy the shares by decreasing value per unit of what they consume: the
+    // price for the benefits alone, the penalized benefits per euro when the
+    // risk is penalized, and price and risk relative to their budgets when
+    // the risk is bounded.
+    let density = |row: &Row| -> Decimal {
+        let (value, weight) = match objective {
+            Objective::Benefits => (row.benefits, row.price),
+            Objective::Penalized { lambda } => (row.benefits - lambda * row.risk, row.price),
+            Objective::RiskBudget { max_risk } => {
+                let mut weight = row.price;
+                if balance > zero!() {
+                    weight /= balance;
+                }
+                if max_risk > zero!() {
+                    weight += row.risk / max_risk;
+                }
+                (row.benefits, weight)
+            }
+        };
+        // a share consuming nothing comes first
+        if weight <= zero!() {
+            return Decimal::MAX;
+        }
+        value / weight
+    };
+    let mut order: Vec<usize> = (0..data.len()).collect();
+    order.sort_by(|a, b| density(&data[*b]).cmp(&density(&data[*a])));
+
+    let mut best = Best {
+        earnings: zero!(),
+        actions: Vec::new(),
+        balance,
+        interrupted: false,
+        shares: Vec::new(),
+    };
+    let mut risk = zero!();
+    for (done, i) in order.into_iter().enumerate() {
+        if search.should_stop() {
+            break;
+        }
+        search.set_covered(done as f64 / data.len() as f64);
+        let row = &data[i];
+        if best.balance >= row.price
+            && objective.allows(risk + row.risk)
+            && objective.score(row.benefits, row.risk) > zero!()
+        {
+            best.earnings += row.benefits;
+            best.balance -= row.price;
+            best.actions.push(i);
+            risk += row.risk;
+            search.offer(&best, objective.score(best.earnings, risk));
+        }
+    }
+    Ok(best)
+}
+
+pub fn show_objective(data: &[Row], best: &Best, objective: Objective) {
+    let risk = total_risk(data, best);
+    println!("Objective : {}", objective);
+    println!("Total risk : {}", risk);
+    println!("Score : {}", objective.score(best.earnings, risk));
+    assert!(objective.allows(risk));
+}
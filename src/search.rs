@@ -0,0 +1,234 @@
+use std::cell::{Cell, RefCell};
+use std::fs::File;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::cancel::Cancellation;
+use crate::Best;
+
+// Observers are told every that many nodes how the search goes
+const PERIOD: u64 = 1 << 12;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Progress {
+    pub nodes: u64,
+    // score of the best result found so far
+    pub best: Option<Decimal>,
+    // estimated fraction of the search tree covered, from 0 to 1
+    pub covered: f64,
+    pub elapsed: Duration,
+}
+
+pub trait Observer {
+    fn progress(&mut self, _progress: &Progress) {}
+    // the incumbent improved
+    fn improved(&mut self, _progress: &Progress, _best: &Best) {}
+    fn finished(&mut self, _progress: &Progress, _best: &Best) {}
+}
+
+// What a solver shares with the outside while it searches: whether it has to
+// stop, and how far it went for the observers.
+#[derive(Default)]
+pub struct Search {
+    cancellation: Cancellation,
+    observers: RefCell<Vec<Box<dyn Observer>>>,
+    start: Option<Instant>,
+    nodes: Cell<u64>,
+    covered: Cell<f64>,
+    best: Cell<Option<Decimal>>,
+}
+
+impl Search {
+    pub fn new(cancellation: Cancellation) -> Search {
+        Search {
+            cancellation,
+            start: Some(Instant::now()),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_observer(self, observer: Box<dyn Observer>) -> Search {
+        self.observers.borrow_mut().push(observer);
+        self
+    }
+
+    fn progress(&self) -> Progress {
+        Progress {
+            nodes: self.nodes.get(),
+            best: self.best.get(),
+            covered: self.covered.get().min(1.0),
+            elapsed: self.start.map(|start| start.elapsed()).unwrap_or_default(),
+        }
+    }
+
+    // Called by the solvers at each node they explore
+    pub fn should_stop(&self) -> bool {
+        let nodes = self.nodes.get() + 1;
+        self.nodes.set(nodes);
+        if nodes.is_multiple_of(PERIOD) && !self.observers.borrow().is_empty() {
+            let progress = self.progress();
+            for observer in self.observers.borrow_mut().iter_mut() {
+                observer.progress(&progress);
+            }
+        }
+        self.cancellation.should_stop()
+    }
+
+    // Whether the solver stopped before the end of its search
+    pub fn stopped(&self) -> bool {
+        self.cancellation.stopped()
+    }
+
+    // A part of the tree done with, as a fraction of the whole tree
+    pub fn cover(&self, fraction: f64) {
+        self.covered.set(self.covered.get() + fraction);
+    }
+
+    // A subtree done with, in the binary tree of the decisions to buy or not
+    // each share, `depth` being the number of decisions above it
+    pub fn cover_subtree(&self, depth: usize) {
+        self.cover(0.5f64.powi(depth.min(i32::MAX as usize) as i32));
+    }
+
+    pub fn set_covered(&self, fraction: f64) {
+        self.covered.set(fraction);
+    }
+
+    // A feasible result met by the solver, the observers are told when its
+    // score is better than the best one so far
+    pub fn offer(&self, best: &Best, score: Decimal) {
+        if self.best.get().is_some_and(|incumbent| incumbent >= score) {
+            return;
+        }
+        self.best.set(Some(score));
+        if self.observers.borrow().is_empty() {
+            return;
+        }
+        let progress = self.progress();
+        for observer in self.observers.borrow_mut().iter_mut() {
+            observer.improved(&progress, best);
+        }
+    }
+
+    pub fn finish(&self, best: &Best) {
+        if !best.interrupted {
+            self.covered.set(1.0);
+        }
+        let progress = self.progress();
+        for observer in self.observers.borrow_mut().iter_mut() {
+            observer.finished(&progress, best);
+        }
+    }
+}
+
+// Progress bar of the fraction of the tree covered, on stderr
+pub struct ProgressObserver {
+    bar: ProgressBar,
+}
+
+impl ProgressObserver {
+    pub fn new() -> Result<ProgressObserver> {
+        let bar = ProgressBar::new(1000);
+        bar.set_style(ProgressStyle::with_template(
+            "{elapsed_precise} [{bar:40}] {percent}% {msg}",
+        )?);
+        Ok(ProgressObserver { bar })
+    }
+
+    fn update(&self, progress: &Progress) {
+        self.bar.set_position((progress.covered * 1000.0) as u64);
+        let best = match progress.best {
+            Some(best) => best.round_dp(2).to_string(),
+            None => "-".to_string(),
+        };
+        self.bar
+            .set_message(format!("{} nodes, best {}", progress.nodes, best));
+    }
+}
+
+impl Observer for ProgressObserver {
+    fn progress(&mut self, progress: &Progress) {
+        self.update(progress);
+    }
+
+    fn improved(&mut self, progress: &Progress, _best: &Best) {
+        self.update(progress);
+    }
+
+    fn finished(&mut self, progress: &Progress, best: &Best) {
+        self.update(progress);
+        if best.interrupted {
+            self.bar.abandon();
+        } else {
+            self.bar.finish();
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Event<'a> {
+    event: &'a str,
+    elapsed_ms: f64,
+    nodes: u64,
+    covered: f64,
+    earnings: f64,
+    balance: f64,
+    shares: Vec<&'a str>,
+    interrupted: bool,
+}
+
+// Every improvement of the incumbent as a JSON line, to follow the run
+pub struct JsonLinesObserver {
+    writer: Box<dyn Write>,
+    // names of the shares, in the order given to the solver
+    names: Vec<String>,
+}
+
+impl JsonLinesObserver {
+    // `-` streams to stdout
+    pub fn new(path: &str, names: Vec<String>) -> Result<JsonLinesObserver> {
+        let writer: Box<dyn Write> = if path == "-" {
+            Box::new(io::stdout())
+        } else {
+            Box::new(File::create(path)?)
+        };
+        Ok(JsonLinesObserver { writer, names })
+    }
+
+    fn write(&mut self, event: &str, progress: &Progress, best: &Best) {
+        let event = Event {
+            event,
+            elapsed_ms: progress.elapsed.as_secs_f64() * 1000.0,
+            nodes: progress.nodes,
+            covered: progress.covered,
+            earnings: best.earnings.to_f64().unwrap_or_default(),
+            balance: best.balance.to_f64().unwrap_or_default(),
+            shares: best
+                .actions
+                .iter()
+                .map(|i| self.names[*i].as_str())
+                .collect(),
+            interrupted: best.interrupted,
+        };
+        // a dashboard gone away does not stop the solver
+        if let Ok(line) = serde_json::to_string(&event) {
+            let _ = writeln!(self.writer, "{}", line).and_then(|_| self.writer.flush());
+        }
+    }
+}
+
+impl Observer for JsonLinesObserver {
+    fn improved(&mut self, progress: &Progress, best: &Best) {
+        self.write("improved", progress, best);
+    }
+
+    fn finished(&mut self, progress: &Progress, best: &Best) {
+        self.write("finished", progress, best);
+    }
+}
@@ -0,0 +1,186 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::Result;
+use rust_decimal::Decimal;
+
+use crate::cancel::Cancellation;
+use crate::risk::Objective;
+use crate::search::Search;
+use crate::{run_algorithme, sort_indexed, Best, Row};
+
+#[derive(Debug, Clone)]
+pub struct Sensitivity {
+    // index of the share in the sorted data
+    pub index: usize,
+    pub chosen: bool,
+    // profit (%) where the selection of the share changes, None when no
+    // profit changes it (always kept, or too expensive to be bought)
+    pub threshold: Option<Decimal>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SensitivityReport {
+    // fewer shares than the data when a Ctrl-C stopped the analysis
+    pub shares: Vec<Sensitivity>,
+    // a re-solve stopped by the time limit or a Ctrl-C, so the thresholds
+    // are not proven
+    pub interrupted: bool,
+}
+
+// The re-solves of the analysis, each with the time limit
+struct Resolve<'a> {
+    algorithme: usize,
+    data: &'a [Row],
+    balance: Decimal,
+    objective: Objective,
+    cancellation: &'a Cancellation,
+    time_limit: Option<Duration>,
+    interrupted: bool,
+}
+
+impl Resolve<'_> {
+    fn is_selected(&mut self, index: usize, profit: Decimal) -> Result<bool> {
+        let mut changed: Vec<(usize, Row)> = self.data.iter().cloned().enumerate().collect();
+        let row = &mut changed[index].1;
+        row.profit = profit;
+        row.benefits = row.price * profit / Decimal::from_str("100").unwrap();
+        // the solvers expect the data sorted by profit
+        let (indexes, changed) = sort_indexed(changed);
+        let search = Search::new(self.cancellation.with_time_limit(self.time_limit));
+        let best = run_algorithme(
+            self.algorithme,
+            changed,
+            self.balance,
+            self.objective,
+            &search,
+        )?;
+        self.interrupted |= best.interrupted;
+        Ok(best.actions.iter().any(|action| indexes[*action] == index))
+    }
+}
+
+fn search_threshold(
+    mut not_selected: Decimal,
+    mut selected: Decimal,
+    is_selected_at: &mut dyn FnMut(Decimal) -> Result<bool>,
+) -> Result<Decimal> {
+    // Bisection down to a hundredth of percent, the precision of the CSV
+    let precision = Decimal::from_str("0.01").unwrap();
+    while (selected - not_selected).abs() > precision {
+        let middle = ((selected + not_selected) / Decimal::from(2)).round_dp(2);
+        if middle == selected || middle == not_selected {
+            break;
+        }
+        if is_selected_at(middle)? {
+            selected = middle;
+        } else {
+            not_selected = middle;
+        }
+    }
+    Ok(selected)
+}
+
+// Each re-solve has the time limit, and a Ctrl-C ends the analysis with the
+// shares done so far
+#[allow(clippy::too_many_arguments)]
+pub fn sensitivity(
+    algorithme: usize,
+    data: &[Row],
+    best: &Best,
+    balance: Decimal,
+    objective: Objective,
+    cancellation: &Cancellation,
+    time_limit: Option<Duration>,
+) -> Result<SensitivityReport> {
+    let mut resolve = Resolve {
+        algorithme,
+        data,
+        balance,
+        objective,
+        cancellation,
+        time_limit,
+        interrupted: false,
+    };
+    let mut shares = Vec::new();
+    for (index, row) in data.iter().enumerate() {
+        if cancellation.is_cancelled() {
+            resolve.interrupted = true;
+            break;
+        }
+        let chosen = best.actions.contains(&index);
+        let mut is_selected_at = |profit| resolve.is_selected(index, profit);
+        let threshold = if chosen {
+            // a share without profit brings nothing, so it is the lower bound
+            if is_selected_at(zero!())? {
+                None
+            } else {
+                Some(search_threshold(zero!(), row.profit, &mut is_selected_at)?)
+            }
+        } else if row.price > balance {
+            None
+        } else {
+            // Once the share alone brings more than the whole portfolio, an
+            // exact solver picks it: that is the upper bound. Heuristics may
+            // need more, so the bound is doubled a few times.
+            let mut upper =
+                best.earnings * Decimal::from_str("100").unwrap() / row.price + Decimal::ONE;
+            let mut found = false;
+            for _ in 0..8 {
+                if is_selected_at(upper)? {
+                    found = true;
+                    break;
+                }
+                upper *= Decimal::from(2);
+            }
+            if found {
+                Some(search_threshold(row.profit, upper, &mut is_selected_at)?)
+            } else {
+                None
+            }
+        };
+        shares.push(Sensitivity {
+            index,
+            chosen,
+            threshold,
+        });
+    }
+    Ok(SensitivityReport {
+        shares,
+        interrupted: resolve.interrupted,
+    })
+}
+
+pub fn show_sensitivity(data: &[Row], report: &SensitivityReport) {
+    println!("Sensitivity of the selection to the profit of each share :");
+    if report.interrupted {
+        println!(
+            "Interrupted : {} of {} shares analysed, thresholds not proven",
+            report.shares.len(),
+            data.len()
+        );
+    }
+    for chosen in [true, false] {
+        for sensitivity in report.shares.iter().filter(|s| s.chosen == chosen) {
+            let row = &data[sensitivity.index];
+            match (chosen, sensitivity.threshold) {
+                (true, Some(threshold)) => println!(
+                    "{} ({}%) : drops out below {}% (margin {}%)",
+                    row.name,
+                    row.profit,
+                    threshold,
+                    row.profit - threshold
+                ),
+                (true, None) => println!("{} ({}%) : kept at any profit", row.name, row.profit),
+                (false, Some(threshold)) => println!(
+                    "{} ({}%) : enters from {}% (margin {}%)",
+                    row.name,
+                    row.profit,
+                    threshold,
+                    threshold - row.profit
+                ),
+                (false, None) => println!("{} ({}%) : never bought", row.name, row.profit),
+            }
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use clap::Args;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::cancel::{self, Cancellation};
+use crate::cli::ObjectiveArgs;
+use crate::risk::{self, Objective};
+use crate::search::Search;
+use crate::{
+    algorithme_name, clean_data, run_algorithme, sort_data, verify, Best, Cleaning, Row,
+    ALGORITHMES, EXACT_ALGORITHMES,
+};
+use crate::{ingest, validate};
+
+/// Serve the solvers as a JSON API : POST /solve, GET /solvers and POST /validate
+#[derive(Args, Debug, Clone)]
+pub struct ServeArgs {
+    /// Address listened to, the local host only by default
+    #[clap(long, default_value = "127.0.0.1")]
+    pub host: String,
+    #[clap(short, long, default_value_t = 8000)]
+    pub port: u16,
+    /// Largest body of a request, in bytes
+    #[clap(long, default_value_t = 1 << 20)]
+    pub max_body: usize,
+    /// Largest number of rows of a request
+    #[clap(long, default_value_t = 10_000)]
+    pub max_rows: usize,
+    /// Seconds given at most to a solver, a request may ask for less with time_limit
+    #[clap(long, parse(try_from_str = cancel::parse_time_limit), default_value = "10")]
+    pub time_limit: Duration,
+    /// Seconds given to a client to send the body of its request, which is then answered 408
+    #[clap(long, parse(try_from_str = cancel::parse_time_limit), default_value = "5")]
+    pub read_timeout: Duration,
+    /// Requests handled at once, the other ones being answered 503
+    #[clap(long, default_value_t = 4)]
+    pub workers: usize,
+}
+
+// Body of POST /solve, the rows being the objects of a JSON dataset
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SolveRequest {
+    rows: Vec<Value>,
+    #[serde(default = "default_balance")]
+    balance: usize,
+    #[serde(default, alias = "solver")]
+    algorithme: usize,
+    #[serde(default)]
+    lambda: Option<Decimal>,
+    #[serde(default)]
+    max_risk: Option<Decimal>,
+    // seconds
+    #[serde(default)]
+    time_limit: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ValidateRequest {
+    rows: Vec<Value>,
+    #[serde(default = "default_balance")]
+    balance: usize,
+}
+
+// the default of --balance
+fn default_balance() -> usize {
+    500
+}
+
+#[derive(Serialize)]
+struct Share<'a> {
+    name: &'a str,
+    // line of the share in the rows of the request
+    line: usize,
+    price: Decimal,
+    profit: Decimal,
+    benefits: Decimal,
+    risk: Decimal,
+    metadata: &'a BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct SolveResponse<'a> {
+    algorithme: usize,
+    solver: &'static str,
+    objective: String,
+    earnings: Decimal,
+    spent: Decimal,
+    leftover: Decimal,
+    shares: Vec<Share<'a>>,
+    // stopped by the time limit, so not proven optimal
+    interrupted: bool,
+    // the earnings and the balance recomputed from the shares match
+    verified: bool,
+    cleaning: &'a Cleaning,
+    duration_ms: f64,
+}
+
+fn parse_body<'a, T: Deserialize<'a>>(body: &'a [u8]) -> Result<T> {
+    match serde_json::from_slice(body) {
+        Ok(request) => Ok(request),
+        Err(error) => bail!("invalid request : {}", error),
+    }
+}
+
+fn check_rows(rows: &[Value], args: &ServeArgs) -> Result<()> {
+    if rows.len() > args.max_rows {
+        bail!(
+            "{} rows, at most {} are accepted",
+            rows.len(),
+            args.max_rows
+        );
+    }
+    Ok(())
+}
+
+fn parse_rows(rows: &[Value], args: &ServeArgs) -> Result<Vec<Row>> {
+    check_rows(rows, args)?;
+    let rows = ingest::parse_json_records(rows)?;
+    Ok(rows.into_iter().map(Row::from).collect())
+}
+
+// The same cleaning, sorting and solver as the solve subcommand
+fn solve_request(body: &[u8], args: &ServeArgs) -> Result<Value> {
+    let request: SolveRequest = parse_body(body)?;
+    if request.algorithme >= ALGORITHMES.len() {
+        bail!(
+            "algorithme number {} does not exist, GET /solvers lists them",
+            request.algorithme
+        );
+    }
+    if request.lambda.is_some() && request.max_risk.is_some() {
+        bail!("lambda and max_risk cannot be given together");
+    }
+    if let Some(lambda) = request.lambda {
+        risk::check_weight("lambda", lambda)?;
+    }
+    if let Some(max_risk) = request.max_risk {
+        risk::check_weight("max_risk", max_risk)?;
+    }
+    let objective = ObjectiveArgs {
+        lambda: request.lambda,
+        max_risk: request.max_risk,
+    }
+    .objective();
+    let time_limit = match request.time_limit {
+        Some(seconds) => cancel::parse_time_limit(&seconds.to_string())?.min(args.time_limit),
+        None => args.time_limit,
+    };
+    let balance: Decimal = request.balance.into();
+    let (mut data, cleaning) = clean_data(parse_rows(&request.rows, args)?);
+    sort_data(&mut data);
+
+    let start = Instant::now();
+    let search = Search::new(Cancellation::none().with_time_limit(Some(time_limit)));
+    let best = run_algorithme(
+        request.algorithme,
+        data.clone(),
+        balance,
+        objective,
+        &search,
+    )?;
+    let duration = start.elapsed();
+    solve_response(
+        request.algorithme,
+        objective,
+        &data,
+        &best,
+        balance,
+        &cleaning,
+        duration,
+    )
+}
+
+// The JSON of a solved run, also written by solve --json
+pub fn solve_response(
+    algorithme: usize,
+    objective: Objective,
+    data: &[Row],
+    best: &Best,
+    balance: Decimal,
+    cleaning: &Cleaning,
+    duration: Duration,
+) -> Result<Value> {
+    let verification = verify(best, data, balance);
+    let response = SolveResponse {
+        algorithme,
+        solver: algorithme_name(algorithme),
+        objective: objective.to_string(),
+        earnings: best.earnings,
+        spent: balance - best.balance,
+        leftover: best.balance,
+        shares: best
+            .actions
+            .iter()
+            .map(|index| {
+                let row = &data[*index];
+                Share {
+                    name: &row.name,
+                    line: row.line,
+                    price: row.price,
+                    profit: row.profit,
+                    benefits: row.benefits,
+                    risk: row.risk,
+                    metadata: &row.metadata,
+                }
+            })
+            .collect(),
+        interrupted: best.interrupted,
+        verified: verification.earnings_match(best)
+            && verification.balance_match(best)
+            && verification.within_budget(),
+        cleaning,
+        duration_ms: duration.as_secs_f64() * 1000.0,
+    };
+    Ok(serde_json::to_value(response)?)
+}
+
+fn solvers() -> Value {
+    let solvers: Vec<Value> = (0..ALGORITHMES.len())
+        .map(|algorithme| {
+            // the risk solvers take the objectives of --lambda and --max-risk
+            let objectives = if algorithme < 5 {
+                vec!["benefits"]
+            } else {
+                vec!["benefits", "lambda", "max_risk"]
+            };
+            json!({
+                "algorithme": algorithme,
+                "name": algorithme_name(algorithme),
+                "exact": EXACT_ALGORITHMES.contains(&algorithme),
+                "objectives": objectives,
+            })
+        })
+        .collect();
+    Value::Array(solvers)
+}
+
+fn validate_request(body: &[u8], args: &ServeArgs) -> Result<Value> {
+    let request: ValidateRequest = parse_body(body)?;
+    // the invalid rows are counted in the statistics, as validate does
+    check_rows(&request.rows, args)?;
+    let (rows, invalid) = ingest::json_records(&request.rows);
+    let data = rows.into_iter().map(Row::from).collect();
+    let statistics = validate::statistics(data, &invalid, request.balance.into());
+    Ok(serde_json::to_value(statistics)?)
+}
+
+fn error(message: impl ToString) -> Value {
+    json!({ "error": message.to_string() })
+}
+
+fn too_large(args: &ServeArgs) -> (u16, Value) {
+    (
+        413,
+        error(format!("body larger than {} bytes", args.max_body)),
+    )
+}
+
+// The status and the JSON answered to a request, apart from the network
+pub fn handle(method: &str, url: &str, body: &[u8], args: &ServeArgs) -> (u16, Value) {
+    if body.len() > args.max_body {
+        return too_large(args);
+    }
+    let path = url.split('?').next().unwrap_or_default();
+    let result = match (method, path) {
+        ("POST", "/solve") => solve_request(body, args),
+        ("GET", "/solvers") => Ok(solvers()),
+        ("POST", "/validate") => validate_request(body, args),
+        (_, "/solve" | "/solvers" | "/validate") => {
+            return (405, error(format!("{} is not allowed on {}", method, path)))
+        }
+        _ => return (404, error(format!("no route {}", path))),
+    };
+    match result {
+        Ok(response) => (200, response),
+        Err(message) => (400, error(message)),
+    }
+}
+
+fn respond(request: Request, status: u16, response: &Value) {
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let response = Response::from_string(response.to_string())
+        .with_status_code(status)
+        .with_header(content_type);
+    // a client gone away is not the server's concern
+    let _ = request.respond(response);
+}
+
+// The request and its body, read by another thread so that a client too slow
+// to send it does not hold a worker: past the read timeout, the reading thread
+// keeps the request and answers 408 once the read ends
+fn read_body(mut request: Request, args: &ServeArgs) -> Option<(Request, io::Result<Vec<u8>>)> {
+    let (sender, receiver) = mpsc::channel();
+    // one byte more than the limit tells a chunked body too large
+    let limit = args.max_body as u64 + 1;
+    let timed_out = error(format!("body not received within {:?}", args.read_timeout));
+    thread::spawn(move || {
+        let mut body = Vec::new();
+        let read = request.as_reader().take(limit).read_to_end(&mut body);
+        if let Err(mpsc::SendError((request, _))) = sender.send((request, read.map(|_| body))) {
+            respond(request, 408, &timed_out);
+        }
+    });
+    receiver.recv_timeout(args.read_timeout).ok()
+}
+
+pub fn handle_request(request: Request, args: &ServeArgs) {
+    let start = Instant::now();
+    let method = request.method().as_str().to_string();
+    let url = request.url().to_string();
+    let (request, (status, response)) = if request.body_length().unwrap_or_default() > args.max_body
+    {
+        (request, too_large(args))
+    } else {
+        match read_body(request, args) {
+            Some((request, Ok(body))) => (request, handle(&method, &url, &body, args)),
+            Some((request, Err(message))) => (request, (400, error(message))),
+            // the reading thread answers once the read ends
+            None => {
+                eprintln!("{} {} 408 {:?}", method, url, start.elapsed());
+                return;
+            }
+        }
+    };
+    eprintln!("{} {} {} {:?}", method, url, status, start.elapsed());
+    respond(request, status, &response);
+}
+
+pub fn serve(args: &ServeArgs) -> Result<()> {
+    let address = format!("{}:{}", args.host, args.port);
+    let server = match Server::http(&address) {
+        Ok(server) => server,
+        Err(error) => bail!("cannot listen on {} : {}", address, error),
+    };
+    println!("Listening on http://{}", address);
+    let busy = Arc::new(AtomicUsize::new(0));
+    for request in server.incoming_requests() {
+        if busy.load(Ordering::SeqCst) >= args.workers {
+            respond(request, 503, &error("too many requests, retry later"));
+            continue;
+        }
+        busy.fetch_add(1, Ordering::SeqCst);
+        let busy = busy.clone();
+        let args = args.clone();
+        thread::spawn(move || {
+            handle_request(request, &args);
+            busy.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+    Ok(())
+}
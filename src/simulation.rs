@@ -0,0 +1,177 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+
+use crate::cancel::Cancellation;
+use crate::risk::Objective;
+use crate::search::Search;
+use crate::validate::quantile;
+use crate::{run_algorithme, sort_indexed, Best, Row};
+
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    // samples drawn, fewer than asked when a Ctrl-C stopped the simulation
+    pub samples: usize,
+    // earnings of the chosen `Best` under each sampled profit
+    pub fixed_earnings: Vec<Decimal>,
+    // earnings of the best portfolio re-solved for each sample
+    pub solved_earnings: Vec<Decimal>,
+    // how many re-solved portfolios contain each share of the data
+    pub selections: Vec<usize>,
+    // a re-solve stopped by the time limit or a Ctrl-C, so not proven optimal
+    pub interrupted: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Distribution {
+    pub mean: Decimal,
+    pub min: Decimal,
+    pub p5: Decimal,
+    pub median: Decimal,
+    pub p95: Decimal,
+    pub max: Decimal,
+}
+
+impl Distribution {
+    pub fn new(values: &[Decimal]) -> Distribution {
+        let mut sorted = values.to_vec();
+        sorted.sort();
+        Distribution {
+            mean: sorted.iter().sum::<Decimal>() / Decimal::from(sorted.len()),
+            min: sorted[0],
+            p5: quantile(&sorted, 5),
+            median: quantile(&sorted, 50),
+            p95: quantile(&sorted, 95),
+            max: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+fn sample_profit(row: &Row, rng: &mut ChaCha8Rng) -> Result<Decimal> {
+    let min = row.profit_min.unwrap_or(row.profit);
+    let max = row.profit_max.unwrap_or(row.profit);
+    if min > max {
+        bail!("{} : profit_min {} > profit_max {}", row.name, min, max);
+    }
+    if min == max {
+        return Ok(min);
+    }
+    let profit = rng.gen_range(min.to_f64().unwrap()..=max.to_f64().unwrap());
+    Ok(Decimal::from_f64(profit).unwrap().round_dp(2))
+}
+
+// Each re-solve has the time limit, and a Ctrl-C ends the simulation with the
+// samples drawn so far
+#[allow(clippy::too_many_arguments)]
+pub fn simulate(
+    algorithme: usize,
+    data: &[Row],
+    best: &Best,
+    balance: Decimal,
+    samples: usize,
+    seed: u64,
+    objective: Objective,
+    cancellation: &Cancellation,
+    time_limit: Option<Duration>,
+) -> Result<Simulation> {
+    if samples == 0 {
+        bail!("the simulation needs at least one sample");
+    }
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut simulation = Simulation {
+        samples: 0,
+        fixed_earnings: Vec::new(),
+        solved_earnings: Vec::new(),
+        selections: vec![0; data.len()],
+        interrupted: false,
+    };
+    for _ in 0..samples {
+        let mut sampled: Vec<(usize, Row)> = Vec::new();
+        for (i, row) in data.iter().enumerate() {
+            let mut row = row.clone();
+            row.profit = sample_profit(&row, &mut rng)?;
+            row.benefits = row.price * row.profit / Decimal::from_str("100").unwrap();
+            sampled.push((i, row));
+        }
+
+        let fixed = best
+            .actions
+            .iter()
+            .map(|i| sampled[*i].1.benefits)
+            .sum::<Decimal>();
+        simulation.fixed_earnings.push(fixed);
+
+        // same cleaning and sorting as the data given to the solvers in main
+        sampled.retain(|(_, row)| row.profit > zero!());
+        let (indexes, sampled) = sort_indexed(sampled);
+        let search = Search::new(cancellation.with_time_limit(time_limit));
+        let solved = run_algorithme(algorithme, sampled, balance, objective, &search)?;
+        simulation.samples += 1;
+        simulation.interrupted |= solved.interrupted;
+        simulation.solved_earnings.push(solved.earnings);
+        for action in solved.actions {
+            simulation.selections[indexes[action]] += 1;
+        }
+        if cancellation.is_cancelled() {
+            break;
+        }
+    }
+    Ok(simulation)
+}
+
+fn show_distribution(title: &str, distribution: &Distribution) {
+    println!(
+        "{} : mean {:.2}€ ; min {:.2}€ ; p5 {:.2}€ ; median {:.2}€ ; p95 {:.2}€ ; max {:.2}€",
+        title,
+        distribution.mean,
+        distribution.min,
+        distribution.p5,
+        distribution.median,
+        distribution.p95,
+        distribution.max,
+    );
+}
+
+pub fn show_simulation(data: &[Row], best: &Best, simulation: &Simulation, seed: u64) {
+    println!(
+        "Simulation of {} samples (seed {}) :",
+        simulation.samples, seed
+    );
+    if simulation.interrupted {
+        println!("Interrupted : re-solved portfolios not proven optimal");
+    }
+    show_distribution(
+        "Chosen portfolio earnings",
+        &Distribution::new(&simulation.fixed_earnings),
+    );
+    show_distribution(
+        "Re-solved earnings",
+        &Distribution::new(&simulation.solved_earnings),
+    );
+    let losses = simulation
+        .fixed_earnings
+        .iter()
+        .filter(|earnings| **earnings < zero!())
+        .count();
+    println!(
+        "Probability of a loss : {:.2}%",
+        losses as f64 * 100.0 / simulation.samples as f64
+    );
+    println!("Selection stability (chosen / % of re-solved portfolios) :");
+    for (i, row) in data.iter().enumerate() {
+        let chosen = best.actions.contains(&i);
+        if chosen || simulation.selections[i] > 0 {
+            println!(
+                "{} {} : {:.1}%",
+                if chosen { "*" } else { " " },
+                row.name,
+                simulation.selections[i] as f64 * 100.0 / simulation.samples as f64
+            );
+        }
+    }
+}
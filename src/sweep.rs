@@ -0,0 +1,106 @@
+use std::fs::File;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use clap::Args;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::cancel::{self, Cancellation};
+use crate::cli::{ObjectiveArgs, SourceArgs};
+use crate::search::Search;
+use crate::{load_dataset, run_algorithme, sort_data};
+
+/// Solve a dataset for a range of balances, to see how the earnings grow with the budget
+#[derive(Args, Debug, Clone)]
+pub struct SweepArgs {
+    #[clap(flatten)]
+    pub source: SourceArgs,
+    #[clap(short, long, default_value_t = 0)]
+    pub algorithme: usize,
+    #[clap(flatten)]
+    pub objective: ObjectiveArgs,
+    /// Smallest balance solved
+    #[clap(long, default_value_t = 100)]
+    pub balance_from: usize,
+    /// Largest balance solved
+    #[clap(long, default_value_t = 1000)]
+    pub balance_to: usize,
+    /// Step between two balances
+    #[clap(long, default_value_t = 100)]
+    pub balance_step: usize,
+    /// CSV file of the results
+    #[clap(long)]
+    pub output: Option<String>,
+    /// Seconds given to each balance, the solver then returning its best result so far
+    #[clap(long, parse(try_from_str = cancel::parse_time_limit))]
+    pub time_limit: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SweepPoint {
+    balance: usize,
+    earnings: Decimal,
+    leftover: Decimal,
+    shares: usize,
+    duration_ns: u128,
+    interrupted: bool,
+}
+
+pub fn sweep(args: &SweepArgs, cancellation: &Cancellation) -> Result<()> {
+    if args.balance_step == 0 || args.balance_from > args.balance_to {
+        bail!(
+            "the balances must go from {} to {} with a positive step",
+            args.balance_from,
+            args.balance_to
+        );
+    }
+    let objective = args.objective.objective();
+    let (mut data, _) = load_dataset(&args.source)?;
+    sort_data(&mut data);
+
+    let mut points = Vec::new();
+    for balance in (args.balance_from..=args.balance_to).step_by(args.balance_step) {
+        let start = Instant::now();
+        let search = Search::new(cancellation.with_time_limit(args.time_limit));
+        let best = run_algorithme(
+            args.algorithme,
+            data.clone(),
+            balance.into(),
+            objective,
+            &search,
+        )?;
+        let point = SweepPoint {
+            balance,
+            earnings: best.earnings,
+            leftover: best.balance,
+            shares: best.actions.len(),
+            duration_ns: start.elapsed().as_nanos(),
+            interrupted: best.interrupted,
+        };
+        println!(
+            "balance {} : earnings {} ; leftover {} ; {} shares ; {}ns{}",
+            point.balance,
+            point.earnings.round_dp(2),
+            point.leftover,
+            point.shares,
+            point.duration_ns,
+            if point.interrupted {
+                " (interrupted)"
+            } else {
+                ""
+            }
+        );
+        points.push(point);
+    }
+
+    if let Some(path) = &args.output {
+        let mut writer = csv::Writer::from_writer(File::create(path)?);
+        for point in &points {
+            writer.serialize(point)?;
+        }
+        writer.flush()?;
+        println!("Sweep written to {}", path);
+    }
+    Ok(())
+}
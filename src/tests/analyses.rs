@@ -0,0 +1,166 @@
+// Analyses re-solving the portfolio, which must keep the time limit of each
+// solve, stop on a Ctrl-C and sort the data as the solve itself
+
+use std::time::{Duration, Instant};
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use super::{row, solve, sorted};
+use crate::cancel::Cancellation;
+use crate::rebalance::{self, Trade};
+use crate::risk::Objective;
+use crate::search::Search;
+use crate::{sensitivity, simulation, sort_indexed, Row};
+
+// Too many shares for the brute force to end within the time limit
+fn large_data(count: usize) -> Vec<Row> {
+    sorted(
+        (0..count)
+            .map(|i| {
+                row(
+                    &format!("Share-{}", i),
+                    Decimal::from(10 + i),
+                    Decimal::from(1 + i % 3),
+                )
+            })
+            .collect(),
+    )
+}
+
+const TIME_LIMIT: Option<Duration> = Some(Duration::from_millis(20));
+
+#[test]
+fn simulation_within_the_time_limit() {
+    let data = large_data(30);
+    let balance = dec!(200);
+    let best = solve(2, &data, balance);
+    let start = Instant::now();
+    let simulation = simulation::simulate(
+        0,
+        &data,
+        &best,
+        balance,
+        3,
+        42,
+        Objective::Benefits,
+        &Cancellation::none(),
+        TIME_LIMIT,
+    )
+    .unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(simulation.samples, 3);
+    assert!(simulation.interrupted);
+}
+
+#[test]
+fn sensitivity_within_the_time_limit() {
+    let data = large_data(24);
+    let balance = dec!(200);
+    let best = solve(2, &data, balance);
+    let start = Instant::now();
+    let report = sensitivity::sensitivity(
+        0,
+        &data,
+        &best,
+        balance,
+        Objective::Benefits,
+        &Cancellation::none(),
+        TIME_LIMIT,
+    )
+    .unwrap();
+    assert!(start.elapsed() < Duration::from_secs(20));
+    assert_eq!(report.shares.len(), data.len());
+    assert!(report.interrupted);
+}
+
+#[test]
+fn analyses_stopped_by_ctrl_c() {
+    let data = large_data(30);
+    let balance = dec!(200);
+    let best = solve(2, &data, balance);
+    let cancellation = Cancellation::none();
+    cancellation.cancel();
+
+    let simulation = simulation::simulate(
+        0,
+        &data,
+        &best,
+        balance,
+        100,
+        42,
+        Objective::Benefits,
+        &cancellation,
+        None,
+    )
+    .unwrap();
+    // the sample being solved is kept, the others are not drawn
+    assert_eq!(simulation.samples, 1);
+    assert!(simulation.interrupted);
+
+    let report = sensitivity::sensitivity(
+        0,
+        &data,
+        &best,
+        balance,
+        Objective::Benefits,
+        &cancellation,
+        None,
+    )
+    .unwrap();
+    assert!(report.shares.is_empty());
+    assert!(report.interrupted);
+}
+
+// The re-solves sorted equal profits in the reverse order of the data, so
+// that a heuristic picked other shares than the solve itself
+#[test]
+fn equal_profits_in_the_order_of_the_data() {
+    let data = sorted(vec![
+        row("Share-C", dec!(25), dec!(20)),
+        row("Share-E", dec!(50), dec!(20)),
+        row("Share-D", dec!(25), dec!(20)),
+        row("Share-A", dec!(50), dec!(20)),
+    ]);
+    let indexed: Vec<(usize, Row)> = data.iter().cloned().enumerate().rev().collect();
+    let (indexes, resorted) = sort_indexed(indexed);
+    assert_eq!(indexes, [0, 1, 2, 3]);
+    assert!(resorted.iter().zip(&data).all(|(a, b)| a.name == b.name));
+
+    let balance = dec!(50);
+    let best = solve(4, &data, balance);
+    let simulation = simulation::simulate(
+        4,
+        &data,
+        &best,
+        balance,
+        1,
+        42,
+        Objective::Benefits,
+        &Cancellation::none(),
+        None,
+    )
+    .unwrap();
+    let selected: Vec<usize> = (0..data.len())
+        .filter(|i| simulation.selections[*i] > 0)
+        .collect();
+    assert_eq!(selected, best.actions);
+
+    let rebalancing = rebalance::rebalance(
+        4,
+        &data,
+        &[],
+        balance,
+        dec!(0),
+        Objective::Benefits,
+        &Search::default(),
+    )
+    .unwrap();
+    let bought: Vec<usize> = rebalancing
+        .trades
+        .iter()
+        .filter(|(trade, _)| *trade == Trade::Buy)
+        .map(|(_, i)| *i)
+        .collect();
+    assert_eq!(bought, best.actions);
+}
@@ -0,0 +1,80 @@
+// The flags of the command line and how they combine
+
+use clap::Parser;
+
+use crate::cancel::Cancellation;
+use crate::cli::{Cli, Command};
+use crate::{solve, sweep};
+
+fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+    Cli::try_parse_from([&["oc_trade"], args].concat())
+}
+
+// the rebalancing ignored the flags of the other outputs
+#[test]
+fn holdings_conflict_with_the_other_outputs() {
+    assert!(parse(&["solve", "--holdings", "held.csv"]).is_ok());
+    for flag in [
+        &["--report", "report.html"][..],
+        &["--explain"],
+        &["--simulate", "10"],
+        &["--sensitivity"],
+        &["--portfolio-chart"],
+        &["--json", "result.json"],
+        &["--json-lines", "-"],
+    ] {
+        let args = [&["solve", "--holdings", "held.csv"][..], flag].concat();
+        assert!(parse(&args).is_err(), "{:?}", flag);
+        // the former interface too
+        assert!(parse(&args[1..]).is_err(), "{:?}", flag);
+    }
+
+    // holdings given by a profile
+    let Ok(Cli {
+        command: Some(Command::Solve(mut args)),
+        ..
+    }) = parse(&["solve", "--explain", "--report", "report.html"])
+    else {
+        panic!("solve expected");
+    };
+    args.holdings = Some("held.csv".to_string());
+    let error = solve(args, &Cancellation::none()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "--explain, --report cannot be used with --holdings"
+    );
+}
+
+// a negative max-risk left no selection, and failed an assertion of the
+// risk solvers, a negative lambda rewarded the risk
+#[test]
+fn negative_risk_weights() {
+    for flag in ["--lambda=-2", "--max-risk=-1"] {
+        let error = parse(&["solve", "-a", "6", flag]).unwrap_err().to_string();
+        assert!(error.contains("must be positive or zero"), "{}", error);
+        assert!(parse(&["compare", flag]).is_err(), "{}", flag);
+    }
+    assert!(parse(&["solve", "-a", "6", "--max-risk", "0"]).is_ok());
+    assert!(parse(&["solve", "-a", "6", "--lambda", "0.5"]).is_ok());
+}
+
+// an unknown solver panicked in every command reaching the solvers
+#[test]
+fn unknown_solver() {
+    for name in ["solve", "sweep"] {
+        let Ok(Cli {
+            command: Some(command),
+            ..
+        }) = parse(&[name, "-a", "9"])
+        else {
+            panic!("{} expected", name);
+        };
+        let error = match command {
+            Command::Solve(args) => solve(args, &Cancellation::none()),
+            Command::Sweep(args) => sweep::sweep(&args, &Cancellation::none()),
+            _ => unreachable!(),
+        }
+        .unwrap_err();
+        assert_eq!(error.to_string(), "no solver 9, expected 0 to 6");
+    }
+}
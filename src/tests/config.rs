@@ -0,0 +1,194 @@
+// The profiles of the configuration, and their precedence over the flags
+
+use std::fs;
+use std::path::PathBuf;
+
+use rust_decimal_macros::dec;
+
+use crate::cli::{Cli, Command};
+use crate::command;
+use crate::config::load_profile;
+
+const CONFIG: &str = "\
+[profile.sienna]
+balance = 100
+dataset = 3
+algorithme = 3
+sell-fees = 0.5
+
+[profile.prudent]
+dataset = 3
+max-risk = 2
+";
+
+// A configuration file of the test, removed once dropped
+struct Config(PathBuf);
+
+impl Config {
+    fn new(name: &str, content: &str) -> Config {
+        let path =
+            std::env::temp_dir().join(format!("oc_trade_{}_{}.toml", name, std::process::id()));
+        fs::write(&path, content).unwrap();
+        Config(path)
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+}
+
+impl Drop for Config {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn parse(args: &[&str]) -> Command {
+    let args = [&["oc_trade"], args].concat();
+    let (cli, matches) = Cli::parse_with_matches(args).unwrap();
+    command(cli, &matches).unwrap()
+}
+
+#[test]
+fn profiles_loaded() {
+    let config = Config::new("profiles", CONFIG);
+    assert!(load_profile(config.path(), "sienna").is_ok());
+    let error = load_profile(config.path(), "bold").unwrap_err().to_string();
+    assert_eq!(
+        error,
+        format!(
+            "no profile bold in {}, the profiles are : prudent, sienna",
+            config.path()
+        )
+    );
+    let error = load_profile("missing.toml", "sienna")
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.starts_with("cannot read the configuration missing.toml : "),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn invalid_profiles() {
+    let config = Config::new("unknown_key", "[profile.sienna]\nbalanse = 100\n");
+    let error = load_profile(config.path(), "sienna")
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("unknown field `balanse`"), "{}", error);
+
+    let config = Config::new("unknown_table", "[profiles.sienna]\nbalance = 100\n");
+    assert!(load_profile(config.path(), "sienna").is_err());
+
+    let config = Config::new(
+        "both_objectives",
+        "[profile.sienna]\nlambda = 1\nmax-risk = 2\n",
+    );
+    let error = load_profile(config.path(), "sienna")
+        .unwrap_err()
+        .to_string();
+    assert_eq!(error, "profile sienna cannot set both lambda and max-risk");
+
+    let config = Config::new("negative_risk", "[profile.sienna]\nmax-risk = -1\n");
+    let error = load_profile(config.path(), "sienna")
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        error,
+        "profile sienna : max-risk must be positive or zero, got -1"
+    );
+}
+
+#[test]
+fn flags_over_profile() {
+    let config = Config::new("precedence", CONFIG);
+    let config = config.path();
+    // --config and --profile on either side of the subcommand
+    for args in [
+        &[
+            "--config",
+            config,
+            "--profile",
+            "sienna",
+            "solve",
+            "-a",
+            "0",
+        ][..],
+        &[
+            "solve",
+            "--config",
+            config,
+            "--profile",
+            "sienna",
+            "-a",
+            "0",
+        ],
+        &[
+            "--config",
+            config,
+            "solve",
+            "--profile",
+            "sienna",
+            "-a",
+            "0",
+        ],
+    ] {
+        let Command::Solve(solve) = parse(args) else {
+            panic!("solve expected");
+        };
+        assert_eq!(solve.algorithme, 0, "{:?}", args);
+        assert_eq!(solve.dataset.balance, 100, "{:?}", args);
+        assert_eq!(solve.dataset.source.dataset, 3, "{:?}", args);
+        assert_eq!(solve.sell_fees, dec!(0.5), "{:?}", args);
+    }
+
+    // the former interface
+    let Command::Solve(solve) = parse(&["--config", config, "--profile", "sienna", "-b", "60"])
+    else {
+        panic!("solve expected");
+    };
+    assert_eq!(solve.dataset.balance, 60);
+    assert_eq!(solve.algorithme, 3);
+
+    // an objective of the command line replaces the one of the profile
+    let Command::Compare(compare) = parse(&[
+        "compare",
+        "--config",
+        config,
+        "--profile",
+        "prudent",
+        "--lambda",
+        "1",
+    ]) else {
+        panic!("compare expected");
+    };
+    assert_eq!(compare.objective.lambda, Some(dec!(1)));
+    assert_eq!(compare.objective.max_risk, None);
+    let Command::Compare(compare) = parse(&["compare", "--config", config, "--profile", "prudent"])
+    else {
+        panic!("compare expected");
+    };
+    assert_eq!(compare.objective.max_risk, Some(dec!(2)));
+
+    // without a profile, the defaults
+    let Command::Solve(solve) = parse(&["--config", config, "solve"]) else {
+        panic!("solve expected");
+    };
+    assert_eq!(solve.dataset.balance, 500);
+    assert_eq!(solve.algorithme, 0);
+}
+
+#[test]
+fn former_flags_before_a_subcommand() {
+    let args = ["oc_trade", "-d", "1", "solve"];
+    let error = Cli::parse_with_matches(args).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("--dataset belongs to the former interface"),
+        "{}",
+        error
+    );
+}
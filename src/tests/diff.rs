@@ -0,0 +1,150 @@
+// The diffs of results, the results read back from their JSON and the solvers
+// named on the command line
+
+use std::time::Duration;
+
+use rust_decimal_macros::dec;
+
+use super::{row, share, solve, sorted};
+use crate::diff::{diff, parse_solver, write_diff, Diff, Selection};
+use crate::risk::Objective;
+use crate::serve::solve_response;
+use crate::{clean_data, ALGORITHMES};
+
+fn selection(shares: &[(&str, usize)], cost: i64, earnings: i64) -> Selection {
+    Selection {
+        shares: shares
+            .iter()
+            .map(|(name, line)| share(name, *line))
+            .collect(),
+        cost: cost.into(),
+        earnings: earnings.into(),
+        leftover: (100 - cost).into(),
+    }
+}
+
+#[test]
+fn diff_of_selections() {
+    let before = selection(&[("Share-B", 3), ("Share-A", 2), ("Share-A", 2)], 60, 10);
+    let after = selection(&[("Share-A", 2), ("Share-C", 4), ("Share-D", 5)], 70, 12);
+    assert_eq!(
+        diff(&before, &after),
+        Diff {
+            added: vec![share("Share-C", 4), share("Share-D", 5)],
+            removed: vec![share("Share-A", 2), share("Share-B", 3)],
+            cost: dec!(10),
+            earnings: dec!(2),
+            leftover: dec!(-10),
+        }
+    );
+    assert!(diff(&after, &after).is_empty());
+}
+
+// Two rows of the same name are two shares, the diff tells them apart
+#[test]
+fn diff_of_shares_of_the_same_name() {
+    let before = selection(&[("Share-A", 2), ("Share-B", 3)], 60, 10);
+    let after = selection(&[("Share-A", 5), ("Share-B", 3)], 60, 10);
+    let diff = diff(&before, &after);
+    assert_eq!(diff.added, [share("Share-A", 5)]);
+    assert_eq!(diff.removed, [share("Share-A", 2)]);
+    let mut out = Vec::new();
+    write_diff(&mut out, &diff).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "Added : Share-A (line 5)\n\
+         Removed : Share-A (line 2)\n\
+         Cost +0 ; earnings +0 ; leftover +0\n"
+    );
+
+    // and so does the JSON of a result
+    let mut data = vec![
+        row("Share-A", dec!(40), dec!(10)),
+        row("Share-A", dec!(30), dec!(20)),
+    ];
+    for (i, row) in data.iter_mut().enumerate() {
+        row.line = i + 2;
+    }
+    let (data, cleaning) = clean_data(data);
+    let data = sorted(data);
+    let balance = dec!(30);
+    let best = solve(0, &data, balance);
+    let result = solve_response(
+        0,
+        Objective::Benefits,
+        &data,
+        &best,
+        balance,
+        &cleaning,
+        Duration::ZERO,
+    )
+    .unwrap();
+    let selection = Selection::from_json(&result.to_string()).unwrap();
+    assert_eq!(selection.shares, [share("Share-A", 3)]);
+}
+
+#[test]
+fn json_result_read_back() {
+    let (data, cleaning) = clean_data(vec![
+        row("Share-A", dec!(40), dec!(10)),
+        row("Share-B", dec!(50), dec!(20)),
+        row("Share-C", dec!(30), dec!(5)),
+    ]);
+    let data = sorted(data);
+    let balance = dec!(80);
+    for algorithme in [0, 3] {
+        let best = solve(algorithme, &data, balance);
+        let result = solve_response(
+            algorithme,
+            Objective::Benefits,
+            &data,
+            &best,
+            balance,
+            &cleaning,
+            Duration::ZERO,
+        )
+        .unwrap();
+        let selection = Selection::from_json(&result.to_string()).unwrap();
+        assert_eq!(selection, Selection::new(&best, balance));
+    }
+}
+
+#[test]
+fn json_result_without_shares() {
+    let error = Selection::from_json(r#"{"earnings": "1", "spent": "2", "leftover": "3"}"#)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("missing field `shares`"), "{}", error);
+}
+
+#[test]
+fn two_solvers_disagreeing() {
+    let data = sorted(vec![
+        row("Share-A", dec!(40), dec!(10)),
+        row("Share-B", dec!(50), dec!(20)),
+        row("Share-C", dec!(30), dec!(5)),
+        row("Share-D", dec!(20), dec!(15)),
+    ]);
+    let balance = dec!(90);
+    let exact = Selection::new(&solve(0, &data, balance), balance);
+    let greedy = Selection::new(&solve(3, &data, balance), balance);
+    let diff = diff(&exact, &greedy);
+    assert!(diff.earnings <= dec!(0));
+    assert_eq!(diff.cost, greedy.cost - exact.cost);
+    assert_eq!(diff.leftover, -diff.cost);
+    assert!(diff.added.iter().all(|share| greedy.shares.contains(share)));
+    assert!(diff
+        .removed
+        .iter()
+        .all(|share| exact.shares.contains(share)));
+}
+
+#[test]
+fn solvers_by_number_or_name() {
+    for (algorithme, name) in ALGORITHMES.iter().enumerate() {
+        assert_eq!(parse_solver(&algorithme.to_string()).unwrap(), algorithme);
+        assert_eq!(parse_solver(name).unwrap(), algorithme);
+    }
+    assert!(parse_solver("7").is_err());
+    assert!(parse_solver("greedy").is_err());
+}
@@ -0,0 +1,244 @@
+// Random small universes, solved by every solver and checked against the
+// brute force, the exact solvers having to buy the same shares. A failing
+// universe is shrunk to a minimal one, written as a CSV under
+// target/differential/ to be replayed with the command line.
+
+use std::fs;
+use std::path::PathBuf;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rust_decimal::Decimal;
+
+use super::{check, row, solve, sorted, EXACT, HEURISTICS};
+use crate::risk::{total_risk, Objective};
+use crate::search::Search;
+use crate::{run_algorithme, Row, ALGORITHMES};
+
+const CASES: usize = 300;
+const SEED: u64 = 42;
+
+#[derive(Debug, Clone)]
+struct Universe {
+    // unsorted, as in a CSV
+    rows: Vec<(Decimal, Decimal)>,
+    balance: Decimal,
+}
+
+impl Universe {
+    fn random(rng: &mut ChaCha8Rng) -> Universe {
+        let balance = Decimal::from(rng.gen_range(0..=200));
+        let n = rng.gen_range(0..=12);
+        let rows = (0..n)
+            .map(|_| {
+                // integer prices make the ties and the exact fits frequent
+                let price = match rng.gen_range(0..4) {
+                    0 if balance > Decimal::ZERO => balance,
+                    1 => Decimal::from(rng.gen_range(1..=100)),
+                    _ => Decimal::new(rng.gen_range(1..=10000), 2),
+                };
+                let profit = Decimal::new(rng.gen_range(1..=4000), 2);
+                (price, profit)
+            })
+            .collect();
+        Universe { rows, balance }
+    }
+
+    fn data(&self) -> Vec<Row> {
+        let data = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(i, (price, profit))| row(&format!("Share-{}", i), *price, *profit))
+            .collect();
+        sorted(data)
+    }
+
+    fn csv(&self) -> String {
+        let mut csv = String::from("name,price,profit\n");
+        for (i, (price, profit)) in self.rows.iter().enumerate() {
+            csv.push_str(&format!("Share-{},{},{}\n", i, price, profit));
+        }
+        csv
+    }
+}
+
+// The first disagreement of a solver with the brute force, if any
+fn failure(universe: &Universe) -> Option<String> {
+    let data = universe.data();
+    let balance = universe.balance;
+    let optimum = solve(0, &data, balance);
+    if let Err(error) = check(0, &optimum, &data, balance) {
+        return Some(error);
+    }
+    for algorithme in EXACT {
+        let best = solve(algorithme, &data, balance);
+        if let Err(error) = check(algorithme, &best, &data, balance) {
+            return Some(error);
+        }
+        if best.earnings != optimum.earnings {
+            return Some(format!(
+                "{} earns {}, the optimum is {}",
+                ALGORITHMES[algorithme], best.earnings, optimum.earnings
+            ));
+        }
+        // the tie-break makes the selection itself unique
+        if best.actions != optimum.actions {
+            return Some(format!(
+                "{} buys {:?}, the brute force {:?}",
+                ALGORITHMES[algorithme], best.actions, optimum.actions
+            ));
+        }
+    }
+    for algorithme in HEURISTICS {
+        let best = solve(algorithme, &data, balance);
+        if let Err(error) = check(algorithme, &best, &data, balance) {
+            return Some(error);
+        }
+        if best.earnings > optimum.earnings {
+            return Some(format!(
+                "{} earns {}, more than the optimum {}",
+                ALGORITHMES[algorithme], best.earnings, optimum.earnings
+            ));
+        }
+    }
+    None
+}
+
+// Removes rows, then rounds the values, as long as the universe still fails
+fn shrink(mut universe: Universe) -> Universe {
+    let mut i = 0;
+    while i < universe.rows.len() {
+        let mut smaller = universe.clone();
+        smaller.rows.remove(i);
+        if failure(&smaller).is_some() {
+            universe = smaller;
+        } else {
+            i += 1;
+        }
+    }
+    for i in 0..universe.rows.len() {
+        for rounded in [
+            (universe.rows[i].0.round(), universe.rows[i].1),
+            (universe.rows[i].0, universe.rows[i].1.round()),
+        ] {
+            if rounded.0 <= Decimal::ZERO || rounded.1 <= Decimal::ZERO {
+                continue;
+            }
+            let mut simpler = universe.clone();
+            simpler.rows[i] = rounded;
+            if failure(&simpler).is_some() {
+                universe = simpler;
+            }
+        }
+    }
+    universe
+}
+
+fn write_case(universe: &Universe) -> PathBuf {
+    let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/differential");
+    let path = directory.join(format!("failure_balance{}.csv", universe.balance));
+    // the panic message holds the CSV anyway
+    let _ = fs::create_dir_all(&directory).and_then(|_| fs::write(&path, universe.csv()));
+    path
+}
+
+#[test]
+fn solvers_agree_with_brute_force() {
+    let mut rng = ChaCha8Rng::seed_from_u64(SEED);
+    for case in 0..CASES {
+        let universe = Universe::random(&mut rng);
+        if failure(&universe).is_none() {
+            continue;
+        }
+        let universe = shrink(universe);
+        let path = write_case(&universe);
+        panic!(
+            "case {} : {}\nbalance {}, written to {}\n{}",
+            case,
+            failure(&universe).unwrap(),
+            universe.balance,
+            path.display(),
+            universe.csv()
+        );
+    }
+}
+
+// The best score of every selection within the balance and the objective
+fn best_score(data: &[Row], balance: Decimal, objective: Objective) -> Decimal {
+    let mut best = Decimal::ZERO;
+    for mask in 0..1usize << data.len() {
+        let rows = || {
+            (0..data.len())
+                .filter(|i| mask & 1 << i != 0)
+                .map(|i| &data[i])
+        };
+        let price: Decimal = rows().map(|row| row.price).sum();
+        let risk: Decimal = rows().map(|row| row.risk).sum();
+        let earnings: Decimal = rows().map(|row| row.benefits).sum();
+        let allowed = match objective {
+            Objective::RiskBudget { max_risk } => risk <= max_risk,
+            _ => true,
+        };
+        if price <= balance && allowed {
+            best = best.max(objective.score(earnings, risk));
+        }
+    }
+    best
+}
+
+// The risk solvers were only checked on the benefits: risk_brut_force must
+// reach the best score under the other objectives, risk_greedy stay within
+// them
+#[test]
+fn risk_solvers_under_each_objective() {
+    let mut rng = ChaCha8Rng::seed_from_u64(SEED);
+    for case in 0..CASES / 3 {
+        let universe = Universe::random(&mut rng);
+        let balance = universe.balance;
+        let mut data = universe.data();
+        for row in &mut data {
+            row.risk = Decimal::new(rng.gen_range(0..=500), 2);
+        }
+        let objectives = [
+            Objective::Penalized {
+                lambda: Decimal::new(rng.gen_range(0..=300), 2),
+            },
+            Objective::RiskBudget {
+                max_risk: Decimal::new(rng.gen_range(0..=1500), 2),
+            },
+        ];
+        for objective in objectives {
+            let optimum = best_score(&data, balance, objective);
+            for algorithme in [5, 6] {
+                let best = run_algorithme(
+                    algorithme,
+                    data.clone(),
+                    balance,
+                    objective,
+                    &Search::default(),
+                )
+                .unwrap();
+                let context = format!(
+                    "case {} : {} under {}, balance {}\n{}",
+                    case,
+                    ALGORITHMES[algorithme],
+                    objective,
+                    balance,
+                    universe.csv()
+                );
+                check(algorithme, &best, &data, balance).expect(&context);
+                let risk = total_risk(&data, &best);
+                if let Objective::RiskBudget { max_risk } = objective {
+                    assert!(risk <= max_risk, "risk {} : {}", risk, context);
+                }
+                let score = objective.score(best.earnings, risk);
+                if algorithme == 5 {
+                    assert_eq!(score, optimum, "{}", context);
+                } else {
+                    assert!(score <= optimum, "score {} : {}", score, context);
+                }
+            }
+        }
+    }
+}
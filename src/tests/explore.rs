@@ -0,0 +1,137 @@
+// The what-if session
+
+use std::fs;
+
+use rust_decimal_macros::dec;
+
+use super::row;
+use crate::cancel::Cancellation;
+use crate::cli::ObjectiveArgs;
+use crate::explore::Session;
+
+fn session() -> Session {
+    session_cancelled_by(Cancellation::none())
+}
+
+fn session_cancelled_by(cancellation: Cancellation) -> Session {
+    let mut data = vec![
+        row("Share-A", dec!(40), dec!(10)),
+        row("Share-B", dec!(50), dec!(20)),
+        row("Share-C", dec!(30), dec!(5)),
+    ];
+    for (i, row) in data.iter_mut().enumerate() {
+        row.line = i + 2;
+    }
+    Session::new(
+        data,
+        dec!(80),
+        0,
+        ObjectiveArgs {
+            lambda: None,
+            max_risk: None,
+        },
+        None,
+        cancellation,
+    )
+}
+
+fn run(session: &mut Session, script: &str) -> String {
+    let mut out = Vec::new();
+    session.run(script.as_bytes(), &mut out, false).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn what_if_scenarios() {
+    let mut session = session();
+    let output = run(
+        &mut session,
+        "solve\nbalance 90\nsolve\nexclude Share-B\nsolve\ndiff\n",
+    );
+    let expected = "\
+Earnings : 11.50 ; spent : 80 ; leftover : 0 ; 2 shares
+Shares : Share-B (line 3), Share-C (line 4)
+Balance : 90
+Earnings : 14.00 ; spent : 90 ; leftover : 0 ; 2 shares
+Shares : Share-B (line 3), Share-A (line 2)
+Compared with the previous result :
+Added : Share-A (line 2)
+Removed : Share-C (line 4)
+Cost +10 ; earnings +2.50 ; leftover +0
+1 shares excluded
+Earnings : 5.50 ; spent : 70 ; leftover : 20 ; 2 shares
+Shares : Share-A (line 2), Share-C (line 4)
+Compared with the previous result :
+Added : Share-C (line 4)
+Removed : Share-B (line 3)
+Cost -20 ; earnings -8.50 ; leftover +20
+Added : Share-C (line 4)
+Removed : Share-B (line 3)
+Cost -20 ; earnings -8.50 ; leftover +20
+";
+    // the durations of the solves change from a run to the other
+    let output: Vec<&str> = output
+        .lines()
+        .filter(|line| !line.starts_with("brut_force_recursive_binary in "))
+        .collect();
+    assert_eq!(output.join("\n") + "\n", expected);
+}
+
+#[test]
+fn errors_keep_the_session() {
+    let mut session = session();
+    let output = run(
+        &mut session,
+        "exclude Share-Z\nsolver 12\nbalance -3\nlambda 1\nsolve\nfly\ninclude Share-A\nquit\nsolve\n",
+    );
+    let errors: Vec<&str> = output
+        .lines()
+        .filter(|line| line.starts_with("Error : "))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "Error : no share Share-Z in the dataset",
+            "Error : no solver 12, solvers lists them",
+            "Error : expected a positive number, got -3",
+            "Error : algorithme number 0 only maximises benefits, use 5 or 6 to maximise benefits − λ·risk with λ = 1",
+            "Error : unknown command fly, help lists them",
+            "Error : Share-A is not excluded",
+        ]
+    );
+    // nothing is solved after quit
+    assert!(!output.contains("Shares :"));
+}
+
+#[test]
+fn transcript_saved() {
+    let path = std::env::temp_dir().join(format!("oc_trade_transcript_{}.txt", std::process::id()));
+    let mut session = session();
+    run(
+        &mut session,
+        &format!("balance 90\nsave {}\n", path.display()),
+    );
+    let transcript = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(
+        transcript,
+        format!("> balance 90\nBalance : 90\n> save {}\n", path.display())
+    );
+}
+
+// the session ignored Ctrl-C, which killed it along with the solve
+#[test]
+fn ctrl_c_stops_one_solve() {
+    let cancellation = Cancellation::none();
+    let mut session = session_cancelled_by(cancellation.clone());
+    cancellation.cancel();
+    let output = run(&mut session, "solve\nsolve\n");
+    let solves: Vec<&str> = output
+        .lines()
+        .filter(|line| line.starts_with("brut_force_recursive_binary in "))
+        .collect();
+    assert_eq!(solves.len(), 2);
+    assert!(solves[0].ends_with(", interrupted : best result found so far, not proven optimal"));
+    assert!(!solves[1].contains("interrupted"));
+    assert!(!cancellation.is_cancelled());
+}
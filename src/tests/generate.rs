@@ -0,0 +1,137 @@
+// The synthetic datasets: reproducible from their seed, and of the
+// relation between the price and the benefits of their class
+
+use clap::Parser;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::cli::{Cli, Command};
+use crate::generate::generate;
+use crate::ingest::{read_records, Format};
+use crate::RowBrut;
+
+// The content of the dataset generated with the given flags
+fn generated(name: &str, flags: &[&str]) -> anyhow::Result<String> {
+    let path = std::env::temp_dir().join(format!(
+        "oc_trade_generated_{}_{}.csv",
+        name,
+        std::process::id()
+    ));
+    let path = path.to_str().unwrap();
+    let args = [&["oc_trade", "generate", path, "--rows", "200"][..], flags].concat();
+    let Ok(Cli {
+        command: Some(Command::Generate(args)),
+        ..
+    }) = Cli::try_parse_from(args)
+    else {
+        panic!("generate expected");
+    };
+    let result = generate(&args).map(|_| std::fs::read_to_string(path).unwrap());
+    let _ = std::fs::remove_file(path);
+    result
+}
+
+fn rows(name: &str, class: &str) -> Vec<RowBrut> {
+    let path = std::env::temp_dir().join(format!(
+        "oc_trade_class_{}_{}.csv",
+        name,
+        std::process::id()
+    ));
+    std::fs::write(&path, generated(name, &["--class", class]).unwrap()).unwrap();
+    let records = read_records(path.to_str().unwrap(), Format::Csv);
+    std::fs::remove_file(&path).unwrap();
+    let (rows, invalid) = records.unwrap();
+    assert!(invalid.is_empty());
+    assert_eq!(rows.len(), 200);
+    assert!(rows
+        .iter()
+        .all(|row| row.price > dec!(0) && row.profit > dec!(0)));
+    rows
+}
+
+fn benefits(row: &RowBrut) -> Decimal {
+    row.price * row.profit / dec!(100)
+}
+
+// the rounding of the price and of the profit to the cent
+const TOLERANCE: Decimal = dec!(0.05);
+
+#[test]
+fn same_seed_same_dataset() {
+    let flags = ["--seed", "7", "--bad-rows", "0.1", "--correlation", "0.5"];
+    let first = generated("first", &flags).unwrap();
+    assert_eq!(first, generated("second", &flags).unwrap());
+    assert_ne!(first, generated("other", &["--seed", "8"]).unwrap());
+}
+
+#[test]
+fn uncorrelated() {
+    let rows = rows("uncorrelated", "uncorrelated");
+    // the profits follow their own uniform distribution around 20%
+    assert!(rows.iter().all(|row| row.profit < dec!(38)));
+    assert!(rows.iter().any(|row| row.profit < dec!(15)));
+    assert!(rows.iter().any(|row| row.profit > dec!(25)));
+}
+
+// with the default means, the benefits are 20% of the price, the largest
+// price being 125
+#[test]
+fn weakly_correlated() {
+    for row in rows("weakly", "weakly-correlated") {
+        let expected = row.price * dec!(0.2);
+        assert!(
+            benefits(&row) <= expected + dec!(2.5) + TOLERANCE,
+            "{:?}",
+            row
+        );
+        assert!(
+            benefits(&row) >= expected - dec!(2.5) - TOLERANCE,
+            "{:?}",
+            row
+        );
+    }
+}
+
+#[test]
+fn strongly_correlated() {
+    for row in rows("strongly", "strongly-correlated") {
+        let expected = row.price * dec!(0.2) + dec!(2.5);
+        assert!((benefits(&row) - expected).abs() <= TOLERANCE, "{:?}", row);
+    }
+}
+
+#[test]
+fn inverse_strongly_correlated() {
+    for row in rows("inverse", "inverse-strongly-correlated") {
+        let expected = (row.price - dec!(12.5)) * dec!(0.2);
+        assert!((benefits(&row) - expected).abs() <= TOLERANCE, "{:?}", row);
+    }
+}
+
+#[test]
+fn subset_sum() {
+    assert!(rows("subset", "subset-sum")
+        .iter()
+        .all(|row| row.profit == dec!(20)));
+}
+
+// the other classes ignored these flags without a word
+#[test]
+fn flags_of_the_uncorrelated_class_only() {
+    assert!(generated("correlation", &["--correlation", "0.5"]).is_ok());
+    for class in [
+        "weakly-correlated",
+        "strongly-correlated",
+        "inverse-strongly-correlated",
+        "subset-sum",
+    ] {
+        for flags in [
+            &["--correlation", "0.5"][..],
+            &["--correlation=-1"],
+            &["--profit-distribution", "lognormal"],
+        ] {
+            let args = [&["--class", class][..], flags].concat();
+            assert!(generated("rejected", &args).is_err(), "{:?}", args);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// The shares of the results known by their name and line, beyond the indices
+// of a run
+
+use std::time::Duration;
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use super::{row, share, solve, sorted, EXACT, HEURISTICS};
+use crate::rebalance::{get_holdings, rebalance, Trade};
+use crate::risk::Objective;
+use crate::search::Search;
+use crate::serve::solve_response;
+use crate::{clean_data, find_share, verify_shares, Cleaning, Row, ShareId};
+
+// Two shares of the same name, told apart by their line only
+fn data() -> Vec<Row> {
+    let mut data = vec![
+        row("Share-A", dec!(40), dec!(10)),
+        row("Share-B", dec!(50), dec!(20)),
+        row("Share-A", dec!(30), dec!(30)),
+        row("Share-C", dec!(60), dec!(5)),
+    ];
+    for (i, row) in data.iter_mut().enumerate() {
+        row.line = i + 2;
+    }
+    sorted(data)
+}
+
+#[test]
+fn shares_of_the_result() {
+    let data = data();
+    let balance = dec!(85);
+    for algorithme in EXACT.into_iter().chain(HEURISTICS) {
+        let best = solve(algorithme, &data, balance);
+        let expected: Vec<ShareId> = best.actions.iter().map(|i| data[*i].id()).collect();
+        assert_eq!(best.shares, expected, "algorithme {}", algorithme);
+        let verification = verify_shares(&best, &data, balance).unwrap();
+        assert_eq!(verification.earnings, best.earnings);
+        assert_eq!(verification.balance, best.balance);
+    }
+    // the line tells which Share-A is bought
+    let best = solve(0, &data, balance);
+    assert_eq!(best.shares, [share("Share-A", 4), share("Share-B", 3)]);
+}
+
+#[test]
+fn unknown_share() {
+    let data = data();
+    assert_eq!(
+        find_share(&data, &share("Share-A", 2)).unwrap().price,
+        dec!(40)
+    );
+    let error = find_share(&data, &share("Share-A", 3)).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "share Share-A (line 3) is not in the dataset"
+    );
+    // the result of another dataset cannot be checked against this one
+    let mut best = solve(0, &data, dec!(85));
+    best.shares[0].line = 9;
+    assert!(verify_shares(&best, &data, dec!(85)).is_err());
+}
+
+#[test]
+fn result_fed_back_as_holdings() {
+    let data = data();
+    let balance = dec!(85);
+    let best = solve(0, &data, balance);
+    let (_, cleaning): (Vec<Row>, Cleaning) = clean_data(Vec::new());
+    let result = solve_response(
+        0,
+        Objective::Benefits,
+        &data,
+        &best,
+        balance,
+        &cleaning,
+        Duration::ZERO,
+    )
+    .unwrap();
+    let path = std::env::temp_dir().join(format!("oc_trade_holdings_{}.json", std::process::id()));
+    std::fs::write(&path, result.to_string()).unwrap();
+    let holdings = get_holdings(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    let holdings = holdings.unwrap();
+
+    // without cash nor fees, the held shares are kept
+    let rebalancing = rebalance(
+        0,
+        &data,
+        &holdings,
+        Decimal::ZERO,
+        Decimal::ZERO,
+        Objective::Benefits,
+        &Search::default(),
+    )
+    .unwrap();
+    let held: Vec<ShareId> = rebalancing
+        .trades
+        .iter()
+        .map(|(trade, i)| {
+            assert_eq!(*trade, Trade::Hold);
+            data[*i].id()
+        })
+        .collect();
+    assert_eq!(held, best.shares);
+}
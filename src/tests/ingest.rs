@@ -0,0 +1,276 @@
+// Reading of datasets as spreadsheets, scripts and upstream systems write them
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rust_decimal_macros::dec;
+
+use crate::ingest::{parse, parse_csv, parse_json, parse_jsonl, parse_toml, Format};
+
+fn prices(content: &[u8]) -> Vec<(String, rust_decimal::Decimal, rust_decimal::Decimal)> {
+    parse_csv(content)
+        .unwrap()
+        .into_iter()
+        .map(|row| (row.name, row.price, row.profit))
+        .collect()
+}
+
+#[test]
+fn comma_separated() {
+    assert_eq!(
+        prices(b"name,price,profit\r\nShare-A,20,5\r\nShare-B,30.5,10\r\n"),
+        vec![
+            ("Share-A".to_string(), dec!(20), dec!(5)),
+            ("Share-B".to_string(), dec!(30.5), dec!(10)),
+        ]
+    );
+}
+
+#[test]
+fn semicolons_and_decimal_commas() {
+    assert_eq!(
+        prices(b"name;price;profit\nShare-A;20,5;5,25\nShare-B;1 234,5;10\n"),
+        vec![
+            ("Share-A".to_string(), dec!(20.5), dec!(5.25)),
+            ("Share-B".to_string(), dec!(1234.5), dec!(10)),
+        ]
+    );
+}
+
+#[test]
+fn tabs_and_quoted_numbers() {
+    assert_eq!(
+        prices(b"name\tprice\tprofit\n\"Share, A\"\t\"1,234.50\"\t\" 5 \"\n"),
+        vec![("Share, A".to_string(), dec!(1234.50), dec!(5))]
+    );
+}
+
+#[test]
+fn byte_order_marks() {
+    let expected = vec![("Share-A".to_string(), dec!(20), dec!(5))];
+    assert_eq!(
+        prices(b"\xef\xbb\xbfname,price,profit\nShare-A,20,5\n"),
+        expected
+    );
+
+    let text = "name,price,profit\nShare-A,20,5\n";
+    let mut little_endian = vec![0xff, 0xfe];
+    let mut big_endian = vec![0xfe, 0xff];
+    for unit in text.encode_utf16() {
+        little_endian.extend(unit.to_le_bytes());
+        big_endian.extend(unit.to_be_bytes());
+    }
+    assert_eq!(prices(&little_endian), expected);
+    assert_eq!(prices(&big_endian), expected);
+}
+
+#[test]
+fn headers_in_any_order_or_case() {
+    let rows =
+        parse_csv(b"Profit, RISK ,Comment,NAME,Price,Profit-Min\n5,2,cheap,Share-A,20,\n").unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].name, "Share-A");
+    assert_eq!(rows[0].price, dec!(20));
+    assert_eq!(rows[0].profit, dec!(5));
+    assert_eq!(rows[0].risk, dec!(2));
+    assert_eq!(rows[0].profit_min, None);
+    assert_eq!(rows[0].metadata.len(), 1);
+    assert_eq!(rows[0].metadata["Comment"], "cheap");
+}
+
+#[test]
+fn blank_lines_and_trailing_delimiters() {
+    assert_eq!(
+        prices(b"name;price;profit;\nShare-A;20;5;\n;;;\n\nShare-B;30;10\n"),
+        vec![
+            ("Share-A".to_string(), dec!(20), dec!(5)),
+            ("Share-B".to_string(), dec!(30), dec!(10)),
+        ]
+    );
+}
+
+// the header is line 1, the skipped lines still count
+#[test]
+fn lines_of_the_rows() {
+    let lines = |content: &[u8], format: Format| -> Vec<usize> {
+        let rows = parse(content, format).unwrap();
+        rows.into_iter().map(|row| row.line).collect()
+    };
+    assert_eq!(
+        lines(
+            b"name;price;profit\nShare-A;20;5\n;;\n\nShare-B;30;10\n",
+            Format::Csv
+        ),
+        [2, 5]
+    );
+    assert_eq!(
+        lines(
+            b"\n{\"name\": \"Share-A\", \"price\": 20, \"profit\": 5}\n{\"name\": \"Share-B\", \"price\": 30, \"profit\": 10}\n",
+            Format::Jsonl
+        ),
+        [2, 3]
+    );
+    assert_eq!(
+        lines(
+            b"[{\"name\": \"Share-A\", \"price\": 20, \"profit\": 5}, {\"name\": \"Share-B\", \"price\": 30, \"profit\": 10}]",
+            Format::Json
+        ),
+        [1, 2]
+    );
+}
+
+#[test]
+fn every_error_with_its_line() {
+    let error =
+        parse_csv(b"name,price,profit\nShare-A,abc,5\nShare-B,20,5\nShare-C,,x\nShare-D,1,2,3\n")
+            .unwrap_err()
+            .to_string();
+    assert!(error.starts_with("4 errors"), "{}", error);
+    assert!(
+        error.contains("line 2 : price : invalid number \"abc\""),
+        "{}",
+        error
+    );
+    assert!(error.contains("line 4 : missing price"), "{}", error);
+    assert!(
+        error.contains("line 4 : profit : invalid number \"x\""),
+        "{}",
+        error
+    );
+    assert!(
+        error.contains("line 5 : 4 fields, the header has 3"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn invalid_headers() {
+    let error = parse_csv(b"name,cost,profit,name\n")
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("duplicated column name"), "{}", error);
+    assert!(error.contains("missing column price"), "{}", error);
+    assert!(parse_csv(b"").is_err());
+    assert!(parse_csv(b"name,price,profit\n\xffShare-A,20,5\n")
+        .unwrap_err()
+        .to_string()
+        .contains("line 2 : not UTF-8"));
+}
+
+#[test]
+fn numbers_out_of_range() {
+    let error = parse_csv(b"name,price,profit\nShare-A,1e20,5\n")
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("price 1e20 is out of range"), "{}", error);
+}
+
+#[test]
+fn json_array_with_metadata() {
+    let rows = parse_json(
+        br#"[
+            {"name": "Share-A", "price": 20.5, "profit": "5,25", "sector": "energy", "isin": null},
+            {"Name": "Share-B", "Price": 30, "Profit": 10, "Risk": 1.5, "rating": 3}
+        ]"#,
+    )
+    .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].price, dec!(20.5));
+    assert_eq!(rows[0].profit, dec!(5.25));
+    assert_eq!(rows[0].metadata.len(), 1);
+    assert_eq!(rows[0].metadata["sector"], "energy");
+    assert_eq!(rows[1].risk, dec!(1.5));
+    assert_eq!(rows[1].metadata["rating"], "3");
+
+    let error = parse_json(br#"[{"name": "Share-A", "price": 20}, 3]"#)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("share 1 : missing profit"), "{}", error);
+    assert!(error.contains("share 2 : expected an object"), "{}", error);
+    assert!(parse_json(br#"{"name": "Share-A"}"#).is_err());
+}
+
+#[test]
+fn json_lines() {
+    let rows = parse_jsonl(
+        b"{\"name\": \"Share-A\", \"price\": 20, \"profit\": 5}\n\n{\"name\": \"Share-B\", \"price\": 30, \"profit\": 10, \"desk\": \"paris\"}\n",
+    )
+    .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[1].metadata["desk"], "paris");
+
+    let error = parse_jsonl(b"{\"name\": \"Share-A\", \"price\": 20, \"profit\": 5}\n{\"name\": \n{\"name\": \"Share-C\", \"price\": \"x\", \"profit\": 5}\n")
+        .unwrap_err()
+        .to_string();
+    assert!(error.starts_with("2 errors"), "{}", error);
+    assert!(error.contains("line 2 : "), "{}", error);
+    assert!(
+        error.contains("line 3 : price : invalid number \"x\""),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn toml_tables() {
+    let rows = parse_toml(
+        b"[[share]]\nname = \"Share-A\"\nprice = 20\nprofit = 5.5\nnote = \"hand written\"\n\n[[share]]\nname = \"Share-B\"\nprice = \"30,5\"\nprofit = 10\n",
+    )
+    .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].profit, dec!(5.5));
+    assert_eq!(rows[0].metadata["note"], "hand written");
+    assert_eq!(rows[1].price, dec!(30.5));
+
+    assert!(parse_toml(b"[[shares]]\nname = \"Share-A\"\n").is_err());
+    assert!(parse_toml(b"share = 3\n").is_err());
+    assert!(parse_toml(b"").unwrap().is_empty());
+}
+
+#[test]
+fn formats_from_extensions() {
+    assert_eq!(Format::from_path("dataset/a.CSV"), Some(Format::Csv));
+    assert_eq!(Format::from_path("export.json"), Some(Format::Json));
+    assert_eq!(Format::from_path("export.ndjson"), Some(Format::Jsonl));
+    assert_eq!(Format::from_path("universe.toml"), Some(Format::Toml));
+    assert_eq!(Format::from_path("export"), None);
+    assert_eq!("jsonl".parse::<Format>().unwrap(), Format::Jsonl);
+    assert!("parquet".parse::<Format>().is_err());
+}
+
+// A short run of what the fuzz target does: mangled files never panic, and
+// whatever is read can be turned into rows for the solvers
+#[test]
+fn mangled_files_do_not_panic() {
+    let seeds: [&[u8]; 6] = [
+        b"name,price,profit\nShare-A,20,5\nShare-B,30.5,10\n",
+        b"\xef\xbb\xbfName;Price;Profit;Risk\n\"Share-A\";\"20,5\";5;1e3\n",
+        b"profit\tname\tprice\tprofit_max\n5\tShare-A\t20\t7\n",
+        br#"[{"name": "Share-A", "price": 20.5, "profit": "5", "x": [1]}]"#,
+        b"{\"name\": \"Share-A\", \"price\": 20, \"profit\": 5}\n",
+        b"[[share]]\nname = \"Share-A\"\nprice = 20\nprofit = 5.5\n",
+    ];
+    let alphabet = b",;\t|\"\n\r.,-e0123456789 []{}:=\xef\xbb\xbf\xff\xfeA";
+    let formats = [Format::Csv, Format::Json, Format::Jsonl, Format::Toml];
+    let mut rng = ChaCha8Rng::seed_from_u64(7);
+    for _ in 0..5000 {
+        let mut content = seeds[rng.gen_range(0..seeds.len())].to_vec();
+        for _ in 0..rng.gen_range(1..8) {
+            let i = rng.gen_range(0..=content.len());
+            let byte = alphabet[rng.gen_range(0..alphabet.len())];
+            match rng.gen_range(0..3) {
+                0 => content.insert(i, byte),
+                1 if i < content.len() => {
+                    content.remove(i);
+                }
+                _ if i < content.len() => content[i] = byte,
+                _ => {}
+            }
+        }
+        for format in formats {
+            if let Ok(rows) = parse(&content, format) {
+                let _: Vec<crate::Row> = rows.into_iter().map(crate::Row::from).collect();
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// Tests of the solvers: random universes checked against the brute force
+// (differential) and the edge cases met so far (regression), and of the
+// reading and the statistics of the datasets (ingest, validate), of the HTTP
+// API (serve), of the what-if session (explore), of the diffs of results
+// (diff), of the identity of the shares of a result (identity), of the
+// analyses re-solving the portfolio (analyses), of the trades of a
+// rebalancing (rebalance), of the synthetic datasets (generate) and of the
+// flags of the command line and the profiles (cli, config).
+
+mod analyses;
+mod cli;
+mod config;
+mod diff;
+mod differential;
+mod explore;
+mod generate;
+mod identity;
+mod ingest;
+mod rebalance;
+mod regression;
+mod serve;
+mod validate;
+
+use rust_decimal::Decimal;
+
+use crate::risk::Objective;
+use crate::search::Search;
+use crate::{
+    run_algorithme, sort_data, verify, Best, Row, RowBrut, ShareId, ALGORITHMES,
+    EXACT_ALGORITHMES as EXACT,
+};
+
+// Solvers which may miss the optimum but must stay feasible
+const HEURISTICS: [usize; 4] = [2, 3, 4, 6];
+
+fn row(name: &str, price: Decimal, profit: Decimal) -> Row {
+    RowBrut {
+        name: name.to_string(),
+        price,
+        profit,
+        profit_min: None,
+        profit_max: None,
+        risk: Decimal::ZERO,
+        metadata: Default::default(),
+        line: 0,
+    }
+    .into()
+}
+
+// A share of a result, as the rows of a dataset are known beyond a run
+fn share(name: &str, line: usize) -> ShareId {
+    ShareId {
+        name: name.to_string(),
+        line,
+    }
+}
+
+// The data as the solvers expect it, sorted by profit
+fn sorted(mut data: Vec<Row>) -> Vec<Row> {
+    sort_data(&mut data);
+    data
+}
+
+fn solve(algorithme: usize, data: &[Row], balance: Decimal) -> Best {
+    run_algorithme(
+        algorithme,
+        data.to_vec(),
+        balance,
+        Objective::Benefits,
+        &Search::default(),
+    )
+    .unwrap()
+}
+
+// What check_data asserts, as an error message instead of a panic
+fn check(algorithme: usize, best: &Best, data: &[Row], balance: Decimal) -> Result<(), String> {
+    let name = ALGORITHMES[algorithme];
+    let mut actions = best.actions.clone();
+    actions.sort_unstable();
+    actions.dedup();
+    if actions.len() != best.actions.len() || actions.iter().any(|i| *i >= data.len()) {
+        return Err(format!(
+            "{} returned invalid actions {:?}",
+            name, best.actions
+        ));
+    }
+    let verification = verify(best, data, balance);
+    if !verification.earnings_match(best) {
+        return Err(format!(
+            "{} claims {} earnings, its actions earn {}",
+            name, best.earnings, verification.earnings
+        ));
+    }
+    if !verification.balance_match(best) {
+        return Err(format!(
+            "{} claims a balance of {}, its actions leave {}",
+            name, best.balance, verification.balance
+        ));
+    }
+    if !verification.within_budget() {
+        return Err(format!("{} spends more than {}", name, balance));
+    }
+    if best.interrupted {
+        return Err(format!("{} was interrupted", name));
+    }
+    Ok(())
+}
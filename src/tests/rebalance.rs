@@ -0,0 +1,56 @@
+// The trades of a rebalancing, from the rows of the dataset before their
+// cleaning
+
+use rust_decimal_macros::dec;
+
+use super::row;
+use crate::rebalance::{get_holdings, rebalance, Trade};
+use crate::risk::Objective;
+use crate::search::Search;
+use crate::{sort_data, Row};
+
+// A held share whose profit turned negative is removed by the cleaning, and
+// still sold for its price less the fees
+#[test]
+fn held_share_removed_by_the_cleaning() {
+    let mut data: Vec<Row> = vec![
+        row("Share-A", dec!(100), dec!(10)),
+        row("Share-B", dec!(100), dec!(-5)),
+        row("Share-C", dec!(50), dec!(20)),
+    ];
+    sort_data(&mut data);
+    let path = std::env::temp_dir().join(format!("oc_trade_sold_{}.csv", std::process::id()));
+    std::fs::write(&path, "name\nShare-A\nShare-B\n").unwrap();
+    let holdings = get_holdings(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    let holdings = holdings.unwrap();
+
+    let rebalancing = rebalance(
+        0,
+        &data,
+        &holdings,
+        dec!(0),
+        dec!(10),
+        Objective::Benefits,
+        &Search::default(),
+    )
+    .unwrap();
+    // both held shares bring 90 once the fees of 10% are paid
+    assert_eq!(rebalancing.budget, dec!(180));
+    assert!(rebalancing.universe.iter().all(|row| row.name != "Share-B"));
+    let mut trades: Vec<(Trade, &str)> = rebalancing
+        .trades
+        .iter()
+        .map(|(trade, i)| (*trade, data[*i].name.as_str()))
+        .collect();
+    trades.sort_by_key(|(_, name)| *name);
+    assert_eq!(
+        trades,
+        [
+            (Trade::Hold, "Share-A"),
+            (Trade::Sell, "Share-B"),
+            (Trade::Buy, "Share-C"),
+        ]
+    );
+    assert_eq!(rebalancing.best.earnings, dec!(20));
+}
@@ -0,0 +1,226 @@
+// Edge cases met by the solvers, each fixed once
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use super::{check, row, solve, sorted, EXACT, HEURISTICS};
+use crate::cli::SourceArgs;
+use crate::{clean_data, get_dataset, Row};
+
+fn all_solvers() -> impl Iterator<Item = usize> {
+    EXACT.into_iter().chain(HEURISTICS)
+}
+
+fn assert_solves(data: &[Row], balance: Decimal, earnings: Decimal) {
+    for algorithme in all_solvers() {
+        let best = solve(algorithme, data, balance);
+        check(algorithme, &best, data, balance).unwrap();
+        assert_eq!(best.earnings, earnings, "algorithme {}", algorithme);
+    }
+}
+
+// brut_force_recursive_binary bought a share only when the balance was
+// strictly greater than its price, unlike brut_force_recursive_redondant
+#[test]
+fn share_priced_at_the_remaining_balance() {
+    let data = sorted(vec![
+        row("Share-A", dec!(30), dec!(10)),
+        row("Share-B", dec!(70), dec!(5)),
+    ]);
+    for algorithme in EXACT {
+        let best = solve(algorithme, &data, dec!(100));
+        check(algorithme, &best, &data, dec!(100)).unwrap();
+        assert_eq!(best.earnings, dec!(6.5), "algorithme {}", algorithme);
+        assert_eq!(best.balance, dec!(0), "algorithme {}", algorithme);
+    }
+}
+
+#[test]
+fn share_costing_exactly_the_budget() {
+    let data = sorted(vec![row("Share-A", dec!(50), dec!(20))]);
+    assert_solves(&data, dec!(50), dec!(10));
+}
+
+#[test]
+fn empty_dataset() {
+    for algorithme in all_solvers() {
+        let best = solve(algorithme, &[], dec!(500));
+        check(algorithme, &best, &[], dec!(500)).unwrap();
+        assert_eq!(best.earnings, dec!(0), "algorithme {}", algorithme);
+        assert!(best.actions.is_empty(), "algorithme {}", algorithme);
+        assert_eq!(best.balance, dec!(500), "algorithme {}", algorithme);
+    }
+}
+
+// the brute force and optimized_recursive_stack reported a balance of 0 when
+// they bought nothing
+#[test]
+fn nothing_affordable() {
+    let data = sorted(vec![
+        row("Share-A", dec!(60), dec!(20)),
+        row("Share-B", dec!(80), dec!(10)),
+    ]);
+    for algorithme in all_solvers() {
+        let best = solve(algorithme, &data, dec!(50));
+        check(algorithme, &best, &data, dec!(50)).unwrap();
+        assert!(best.actions.is_empty(), "algorithme {}", algorithme);
+        assert_eq!(best.balance, dec!(50), "algorithme {}", algorithme);
+    }
+}
+
+#[test]
+fn zero_budget() {
+    let data = sorted(vec![row("Share-A", dec!(1), dec!(1))]);
+    assert_solves(&data, dec!(0), dec!(0));
+}
+
+// The selection among equal optima depended on the traversal of each solver,
+// the tie-break now prefers the lower cost, the fewer shares then the names
+// and the lines.
+// optimized_recursive_stack applies it to the selections it compares.
+#[test]
+fn equal_optima_tie_break() {
+    let names = |data: &[Row], balance: Decimal, algorithme: usize| -> Vec<String> {
+        let best = solve(algorithme, data, balance);
+        check(algorithme, &best, data, balance).unwrap();
+        best.actions.iter().map(|i| data[*i].name.clone()).collect()
+    };
+    // lower cost : Share-A and Share-B both earn 10
+    let data = sorted(vec![
+        row("Share-B", dec!(100), dec!(10)),
+        row("Share-A", dec!(50), dec!(20)),
+    ]);
+    for algorithme in EXACT.into_iter().chain([4]) {
+        assert_eq!(names(&data, dec!(100), algorithme), ["Share-A"]);
+    }
+    // fewer shares then names : Share-C and Share-D together, Share-E and
+    // Share-A each earn 10 for 50. optimized_recursive_stack keeps the first
+    // subtree improving, Share-C and Share-D, and never compares the others.
+    let data = sorted(vec![
+        row("Share-C", dec!(25), dec!(20)),
+        row("Share-E", dec!(50), dec!(20)),
+        row("Share-D", dec!(25), dec!(20)),
+        row("Share-A", dec!(50), dec!(20)),
+    ]);
+    for algorithme in EXACT {
+        assert_eq!(names(&data, dec!(50), algorithme), ["Share-A"]);
+    }
+    // then lines : the rows of the same name compared equal, as Share-GUFP
+    // and Share-NJGR of the dataset 2
+    let mut data = vec![
+        row("Share-A", dec!(50), dec!(20)),
+        row("Share-A", dec!(50), dec!(20)),
+    ];
+    data[0].line = 2;
+    data[1].line = 3;
+    let data = sorted(data);
+    for algorithme in EXACT {
+        let best = solve(algorithme, &data, dec!(50));
+        let lines: Vec<usize> = best.actions.iter().map(|i| data[*i].line).collect();
+        assert_eq!(lines, [2], "algorithme {}", algorithme);
+    }
+}
+
+// sort_data reversed a sort by profit, so that equal profits came in the
+// reverse order of the dataset
+#[test]
+fn canonical_order_of_the_data() {
+    let data = sorted(vec![
+        row("Share-B", dec!(10), dec!(5)),
+        row("Share-C", dec!(20), dec!(5)),
+        row("Share-A", dec!(20), dec!(5)),
+        row("Share-D", dec!(10), dec!(8)),
+    ]);
+    let names: Vec<&str> = data.iter().map(|row| row.name.as_str()).collect();
+    assert_eq!(names, ["Share-D", "Share-B", "Share-A", "Share-C"]);
+}
+
+// optimized_recursive_stack kept the shares whose subtree did not improve
+// next to the ones replacing them, so that check_data panicked on datasets 1
+// and 2 : its selection there is pinned
+#[test]
+fn stack_solver_on_the_datasets() {
+    let pinned: [(usize, Decimal, &[&str]); 2] = [
+        (
+            1,
+            dec!(198.513430),
+            &[
+                "Share-XJMO",
+                "Share-MTLR",
+                "Share-KMTG",
+                "Share-GTQK",
+                "Share-LRBZ",
+                "Share-WPLI",
+                "Share-GIAJ",
+                "Share-GHIZ",
+                "Share-ZSDE",
+                "Share-IFCP",
+                "Share-FKJW",
+                "Share-NHWA",
+                "Share-LPDM",
+                "Share-QQTU",
+                "Share-USSR",
+                "Share-EMOV",
+                "Share-LGWG",
+                "Share-QLMK",
+                "Share-SKKC",
+                "Share-UEZB",
+                "Share-CBNY",
+                "Share-CGJM",
+                "Share-EVUW",
+                "Share-FHZN",
+                "Share-DBUJ",
+            ],
+        ),
+        (
+            2,
+            dec!(197.768345),
+            &[
+                "Share-PATS",
+                "Share-ALIY",
+                "Share-JWGF",
+                "Share-PLLK",
+                "Share-NDKR",
+                "Share-FWBE",
+                "Share-LFXB",
+                "Share-ZOFA",
+                "Share-ANFX",
+                "Share-LXZU",
+                "Share-FAPS",
+                "Share-XQII",
+                "Share-ECAQ",
+                "Share-JGTW",
+                "Share-IXCI",
+                "Share-DWSK",
+                "Share-ROOM",
+                "Share-VCXT",
+                "Share-YFVZ",
+                "Share-OCKK",
+                "Share-JMLZ",
+                "Share-DYVD",
+            ],
+        ),
+    ];
+    let balance = dec!(500);
+    for (dataset, earnings, shares) in pinned {
+        let source = SourceArgs {
+            dataset,
+            input: None,
+            format: None,
+        };
+        let (data, _) = clean_data(get_dataset(&source).unwrap());
+        let data = sorted(data);
+        let best = solve(4, &data, balance);
+        check(4, &best, &data, balance).unwrap();
+        assert_eq!(best.earnings, earnings, "dataset {}", dataset);
+        let mut names: Vec<&str> = best
+            .shares
+            .iter()
+            .map(|share| share.name.as_str())
+            .collect();
+        let mut expected = shares.to_vec();
+        names.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(names, expected, "dataset {}", dataset);
+    }
+}
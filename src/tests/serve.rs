@@ -0,0 +1,208 @@
+// The HTTP API, through its handler
+
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::time::{Duration, Instant};
+
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+use crate::cli::SourceArgs;
+use crate::risk::Objective;
+use crate::search::Search;
+use crate::serve::{handle, handle_request, ServeArgs};
+use crate::{clean_data, get_dataset, run_algorithme, sort_data};
+
+fn args() -> ServeArgs {
+    ServeArgs {
+        host: "127.0.0.1".to_string(),
+        port: 0,
+        max_body: 1 << 16,
+        max_rows: 50,
+        time_limit: Duration::from_secs(10),
+        read_timeout: Duration::from_secs(5),
+        workers: 1,
+    }
+}
+
+fn post(url: &str, body: &Value) -> (u16, Value) {
+    handle("POST", url, body.to_string().as_bytes(), &args())
+}
+
+fn decimal(value: &Value) -> Decimal {
+    value.as_str().unwrap().parse().unwrap()
+}
+
+#[test]
+fn solve_as_the_command_line() {
+    let source = SourceArgs {
+        dataset: 3,
+        input: None,
+        format: None,
+    };
+    let data = get_dataset(&source).unwrap();
+    let rows: Vec<Value> = data
+        .iter()
+        .map(|row| json!({"name": row.name, "price": row.price.to_string(), "profit": row.profit.to_string()}))
+        .collect();
+
+    let (mut data, _) = clean_data(data);
+    sort_data(&mut data);
+    for algorithme in 0..5 {
+        let best = run_algorithme(
+            algorithme,
+            data.clone(),
+            Decimal::from(60),
+            Objective::Benefits,
+            &Search::default(),
+        )
+        .unwrap();
+        let (status, response) = post(
+            "/solve",
+            &json!({"rows": rows, "balance": 60, "algorithme": algorithme}),
+        );
+        assert_eq!(status, 200, "{}", response);
+        assert_eq!(decimal(&response["earnings"]), best.earnings);
+        assert_eq!(decimal(&response["leftover"]), best.balance);
+        let names: Vec<&str> = best
+            .actions
+            .iter()
+            .map(|i| data[*i].name.as_str())
+            .collect();
+        let shares: Vec<&str> = response["shares"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|share| share["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(shares, names);
+        assert_eq!(response["verified"], true);
+        assert_eq!(response["interrupted"], false);
+    }
+}
+
+#[test]
+fn solvers_listed() {
+    let (status, response) = handle("GET", "/solvers", b"", &args());
+    assert_eq!(status, 200);
+    let solvers = response.as_array().unwrap();
+    assert_eq!(solvers.len(), 7);
+    assert_eq!(solvers[0]["name"], "brut_force_recursive_binary");
+    assert_eq!(solvers[0]["exact"], true);
+    assert_eq!(solvers[3]["exact"], false);
+}
+
+#[test]
+fn validate_statistics() {
+    let (status, response) = post(
+        "/validate",
+        &json!({"rows": [
+            {"name": "Share-A", "price": 20, "profit": 5},
+            {"name": "Share-A", "price": 30, "profit": 10},
+            {"name": "Share-B", "price": 0, "profit": 10},
+        ], "balance": 25}),
+    );
+    assert_eq!(status, 200, "{}", response);
+    assert_eq!(response["cleaning"]["non_positive_price"], 1);
+    assert_eq!(response["duplicates"]["Share-A"], 2);
+    assert_eq!(response["affordable"], 1);
+    assert_eq!(decimal(&response["total_cost"]), Decimal::from(50));
+}
+
+// an invalid row answered a 400 instead of being counted as the command line does
+#[test]
+fn validate_invalid_rows() {
+    let (status, response) = post(
+        "/validate",
+        &json!({"rows": [
+            {"name": "Share-A", "price": 20, "profit": 5},
+            {"name": "Share-B", "price": "x", "profit": 10},
+            {"name": "Share-C", "price": 30},
+        ], "balance": 25}),
+    );
+    assert_eq!(status, 200, "{}", response);
+    assert_eq!(response["rejection"]["records"], 2);
+    assert_eq!(response["rejection"]["invalid_number"], 1);
+    assert_eq!(response["rejection"]["missing_field"], 1);
+    assert_eq!(decimal(&response["total_cost"]), Decimal::from(20));
+}
+
+#[test]
+fn time_limit_of_the_request() {
+    let rows: Vec<Value> = (1..=40)
+        .map(|i| json!({"name": format!("Share-{}", i), "price": i, "profit": 40 - i % 7}))
+        .collect();
+    let (status, response) = post(
+        "/solve",
+        &json!({"rows": rows, "balance": 400, "algorithme": 1, "time_limit": 0.05}),
+    );
+    assert_eq!(status, 200, "{}", response);
+    assert_eq!(response["interrupted"], true);
+    assert_eq!(response["verified"], true);
+}
+
+#[test]
+fn invalid_requests() {
+    let share = json!({"name": "Share-A", "price": 20, "profit": 5});
+    let cases = [
+        (json!({"rows": [share], "algorithme": 7}), "does not exist"),
+        (
+            json!({"rows": [share], "lambda": 1, "max_risk": 2}),
+            "cannot be given together",
+        ),
+        (
+            json!({"rows": [share], "algorithme": 0, "lambda": 1}),
+            "only maximises",
+        ),
+        (json!({"rows": [share], "budget": 3}), "unknown field"),
+        (
+            json!({"rows": [{"name": "Share-A", "price": "x"}]}),
+            "missing profit",
+        ),
+        (
+            json!({"rows": [share], "algorithme": 6, "lambda": -2}),
+            "lambda must be positive or zero",
+        ),
+        (
+            json!({"rows": [share], "algorithme": 6, "max_risk": -1}),
+            "max_risk must be positive or zero",
+        ),
+        (json!({"rows": vec![share; 51]}), "at most 50"),
+    ];
+    for (body, message) in cases {
+        let (status, response) = post("/solve", &body);
+        assert_eq!(status, 400, "{}", body);
+        let error = response["error"].as_str().unwrap();
+        assert!(error.contains(message), "{} : {}", body, error);
+    }
+    assert_eq!(handle("POST", "/solve", b"{", &args()).0, 400);
+    assert_eq!(handle("GET", "/solve", b"", &args()).0, 405);
+    assert_eq!(handle("GET", "/portfolio", b"", &args()).0, 404);
+    let large = vec![b' '; (1 << 16) + 1];
+    assert_eq!(handle("POST", "/solve", &large, &args()).0, 413);
+}
+
+// a client sending its body slower than it announced held a worker forever
+#[test]
+fn body_read_timeout() {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let mut client = TcpStream::connect(server.server_addr().to_ip().unwrap()).unwrap();
+    // above 1 kB the body is not read along with the headers
+    client
+        .write_all(b"POST /validate HTTP/1.1\r\nHost: localhost\r\nContent-Length: 2000\r\n\r\n{")
+        .unwrap();
+    let request = server.recv().unwrap();
+    let args = ServeArgs {
+        read_timeout: Duration::from_millis(50),
+        ..args()
+    };
+    let start = Instant::now();
+    handle_request(request, &args);
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    // the request is answered once the client stops sending
+    client.shutdown(Shutdown::Write).unwrap();
+    let mut answer = String::new();
+    client.read_to_string(&mut answer).unwrap();
+    assert!(answer.starts_with("HTTP/1.1 408"), "{}", answer);
+}
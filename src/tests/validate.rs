@@ -0,0 +1,88 @@
+// Statistics shown by the validate subcommand
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use super::row;
+use crate::ingest::{parse_records, Format};
+use crate::simulation;
+use crate::validate::{distribution, duplicate_names, statistics, Distribution, Rejection};
+use crate::Row;
+
+#[test]
+fn quantiles_by_nearest_rank() {
+    let values: Vec<Decimal> = (1..=10).rev().map(Decimal::from).collect();
+    assert_eq!(
+        distribution(&values),
+        Some(Distribution {
+            min: dec!(1),
+            q1: dec!(3),
+            median: dec!(5),
+            q3: dec!(8),
+            max: dec!(10),
+            mean: dec!(5.5),
+        })
+    );
+    let single = distribution(&[dec!(4.2)]).unwrap();
+    assert_eq!(
+        (single.min, single.median, single.max),
+        (dec!(4.2), dec!(4.2), dec!(4.2))
+    );
+    assert_eq!(distribution(&[]), None);
+}
+
+// validate and the simulation share the same quantiles
+#[test]
+fn quantiles_of_the_simulation() {
+    let values: Vec<Decimal> = (1..=10).map(Decimal::from).collect();
+    let simulated = simulation::Distribution::new(&values);
+    let described = distribution(&values).unwrap();
+    assert_eq!(simulated.median, described.median);
+    assert_eq!((simulated.p5, simulated.p95), (dec!(1), dec!(10)));
+}
+
+#[test]
+fn invalid_rows_counted_by_rule() {
+    let content = "name,price,profit\n\
+                   Share-A,20,5\n\
+                   Share-B,abc,4\n\
+                   Share-C,30\n\
+                   Share-D,10,2,9\n\
+                   Share-E,x,\n\
+                   Share-F,40,10\n";
+    let (rows, invalid) = parse_records(content.as_bytes(), Format::Csv).unwrap();
+    let data: Vec<Row> = rows.into_iter().map(Row::from).collect();
+    let statistics = statistics(data, &invalid, dec!(500));
+    assert_eq!(
+        statistics.rejection,
+        Rejection {
+            records: 4,
+            invalid_number: 2,
+            missing_field: 2,
+            extra_fields: 1,
+            ..Default::default()
+        }
+    );
+    // the valid rows are still described
+    assert_eq!(statistics.cleaning.kept, 2);
+    let price = statistics.price.unwrap();
+    assert_eq!((price.min, price.max), (dec!(20), dec!(40)));
+}
+
+#[test]
+fn duplicates_counted_by_name() {
+    let data = vec![
+        row("Share-B", dec!(1), dec!(1)),
+        row("Share-A", dec!(1), dec!(1)),
+        row("Share-B", dec!(2), dec!(1)),
+        row("Share-C", dec!(1), dec!(1)),
+        row("Share-B", dec!(3), dec!(1)),
+        row("Share-A", dec!(1), dec!(1)),
+    ];
+    let duplicates: Vec<(String, usize)> = duplicate_names(&data).into_iter().collect();
+    assert_eq!(
+        duplicates,
+        vec![("Share-A".to_string(), 2), ("Share-B".to_string(), 3)]
+    );
+    assert!(duplicate_names(&data[..2]).is_empty());
+}
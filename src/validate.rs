@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+use clap::Args;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::cli::DatasetArgs;
+use crate::ingest::{self, Invalid, Rule};
+use crate::{clean_data, Cleaning, Row};
+
+/// Check that a dataset can be read, what its cleaning removes, and describe its shares
+#[derive(Args, Debug, Clone)]
+pub struct ValidateArgs {
+    #[clap(flatten)]
+    pub dataset: DatasetArgs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Distribution {
+    pub min: Decimal,
+    pub q1: Decimal,
+    pub median: Decimal,
+    pub q3: Decimal,
+    pub max: Decimal,
+    pub mean: Decimal,
+}
+
+// Quantile of values sorted and not empty, by the nearest rank so that it is
+// one of the values. The simulation uses it as well.
+pub fn quantile(sorted: &[Decimal], percent: usize) -> Decimal {
+    let rank = (percent * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+pub fn distribution(values: &[Decimal]) -> Option<Distribution> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort();
+    let sum: Decimal = sorted.iter().sum();
+    Some(Distribution {
+        min: sorted[0],
+        q1: quantile(&sorted, 25),
+        median: quantile(&sorted, 50),
+        q3: quantile(&sorted, 75),
+        max: sorted[sorted.len() - 1],
+        mean: sum / Decimal::from(sorted.len()),
+    })
+}
+
+// Records that could not be read as shares, by the rule they break, a
+// record may break several
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Rejection {
+    pub records: usize,
+    pub invalid_number: usize,
+    pub out_of_range: usize,
+    pub missing_field: usize,
+    pub duplicated_field: usize,
+    pub extra_fields: usize,
+    pub unreadable: usize,
+}
+
+pub fn rejection(invalid: &[Invalid]) -> Rejection {
+    let mut rejection = Rejection {
+        records: invalid.len(),
+        ..Default::default()
+    };
+    for record in invalid {
+        let breaks = |rule: Rule| record.errors.iter().any(|(broken, _)| *broken == rule);
+        let counts = [
+            (Rule::InvalidNumber, &mut rejection.invalid_number),
+            (Rule::OutOfRange, &mut rejection.out_of_range),
+            (Rule::MissingField, &mut rejection.missing_field),
+            (Rule::DuplicatedField, &mut rejection.duplicated_field),
+            (Rule::ExtraFields, &mut rejection.extra_fields),
+            (Rule::Unreadable, &mut rejection.unreadable),
+        ];
+        for (rule, count) in counts {
+            if breaks(rule) {
+                *count += 1;
+            }
+        }
+    }
+    rejection
+}
+
+// The names given to several rows, with their number of rows
+pub fn duplicate_names(data: &[Row]) -> BTreeMap<String, usize> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for row in data {
+        *counts.entry(row.name.clone()).or_default() += 1;
+    }
+    counts.retain(|_, count| *count > 1);
+    counts
+}
+
+// What validate shows of a dataset, the distributions being the ones of the
+// rows kept by the cleaning
+#[derive(Debug, Clone, Serialize)]
+pub struct Statistics {
+    pub rejection: Rejection,
+    pub cleaning: Cleaning,
+    pub duplicates: BTreeMap<String, usize>,
+    pub price: Option<Distribution>,
+    pub profit: Option<Distribution>,
+    pub benefits: Option<Distribution>,
+    pub balance: Decimal,
+    // shares whose price is within the balance
+    pub affordable: usize,
+    pub total_cost: Decimal,
+}
+
+// The statistics of the valid rows, besides the records rejected
+pub fn statistics(data: Vec<Row>, invalid: &[Invalid], balance: Decimal) -> Statistics {
+    let duplicates = duplicate_names(&data);
+    let (data, cleaning) = clean_data(data);
+    let column = |value: fn(&Row) -> Decimal| -> Vec<Decimal> { data.iter().map(value).collect() };
+    Statistics {
+        rejection: rejection(invalid),
+        cleaning,
+        duplicates,
+        price: distribution(&column(|row| row.price)),
+        profit: distribution(&column(|row| row.profit)),
+        benefits: distribution(&column(|row| row.benefits)),
+        balance,
+        affordable: data.iter().filter(|row| row.price <= balance).count(),
+        total_cost: data.iter().map(|row| row.price).sum(),
+    }
+}
+
+fn show_distribution(name: &str, distribution: &Option<Distribution>) {
+    if let Some(distribution) = distribution {
+        println!(
+            "{:<12} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            name,
+            distribution.min.round_dp(2),
+            distribution.q1.round_dp(2),
+            distribution.median.round_dp(2),
+            distribution.q3.round_dp(2),
+            distribution.max.round_dp(2),
+            distribution.mean.round_dp(2)
+        );
+    }
+}
+
+pub fn show_statistics(statistics: &Statistics) {
+    let cleaning = &statistics.cleaning;
+    let rejection = &statistics.rejection;
+    println!("Rows read : {}", cleaning.rows);
+    if rejection.records > 0 {
+        let rules: Vec<String> = [
+            ("invalid number", rejection.invalid_number),
+            ("out of range", rejection.out_of_range),
+            ("missing field", rejection.missing_field),
+            ("duplicated field", rejection.duplicated_field),
+            ("extra fields", rejection.extra_fields),
+            ("unreadable", rejection.unreadable),
+        ]
+        .iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(rule, count)| format!("{} {}", rule, count))
+        .collect();
+        println!(
+            "Invalid rows : {} ({})",
+            rejection.records,
+            rules.join(", ")
+        );
+    }
+    println!("Non positive price : {}", cleaning.non_positive_price);
+    println!("Non positive profit : {}", cleaning.non_positive_profit);
+    println!("Rows kept : {}", cleaning.kept);
+    if statistics.duplicates.is_empty() {
+        println!("Duplicate names : none");
+    } else {
+        let names: Vec<String> = statistics
+            .duplicates
+            .iter()
+            .map(|(name, count)| format!("{} ({} rows)", name, count))
+            .collect();
+        println!("Duplicate names : {}", names.join(", "));
+    }
+    if cleaning.kept == 0 {
+        return;
+    }
+
+    println!();
+    println!(
+        "{:<12} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "", "min", "q1", "median", "q3", "max", "mean"
+    );
+    show_distribution("price", &statistics.price);
+    show_distribution("profit (%)", &statistics.profit);
+    show_distribution("benefits", &statistics.benefits);
+    println!();
+
+    let balance = statistics.balance;
+    let total = statistics.total_cost;
+    println!(
+        "Affordable shares : {} of {} under a balance of {}",
+        statistics.affordable, cleaning.kept, balance
+    );
+    if total <= balance {
+        println!(
+            "Total cost : {}, within the balance of {} so every share can be bought",
+            total, balance
+        );
+    } else if balance > Decimal::ZERO {
+        println!(
+            "Total cost : {}, {} times the balance of {}",
+            total,
+            (total / balance).round_dp(2),
+            balance
+        );
+    } else {
+        println!("Total cost : {}, for a balance of 0", total);
+    }
+}
+
+// The invalid rows do not stop the statistics of the valid ones, but fail
+// the validation after them
+pub fn validate(args: &ValidateArgs) -> Result<()> {
+    let source = &args.dataset.source;
+    let path = source.path();
+    let (rows, invalid) = ingest::read_records(&path, source.format()?)?;
+    let data: Vec<Row> = rows.into_iter().map(Row::from).collect();
+    let statistics = statistics(data, &invalid, args.dataset.balance.into());
+    show_statistics(&statistics);
+    if let Err(error) = ingest::rows_or_errors(Vec::new(), &invalid) {
+        bail!("invalid dataset {} : {}", path, error);
+    }
+    if statistics.cleaning.kept == 0 {
+        bail!("no share of {} can be bought", path);
+    }
+    Ok(())
+}